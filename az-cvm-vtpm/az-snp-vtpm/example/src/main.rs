@@ -5,7 +5,6 @@ use az_snp_vtpm::certs::Vcek;
 use az_snp_vtpm::hcl::HclReport;
 use az_snp_vtpm::report::{AttestationReport, Validateable};
 use az_snp_vtpm::{amd_kds, imds, vtpm};
-use openssl::pkey::PKey;
 use std::error::Error;
 
 struct Evidence {
@@ -19,6 +18,7 @@ struct Attester;
 impl Attester {
     fn gather_evidence(nonce: &[u8]) -> Result<Evidence, Box<dyn Error>> {
         let report = vtpm::get_report()?;
+        vtpm::verify_ak_pub(&HclReport::new(report.clone())?)?;
         let quote = vtpm::get_quote(nonce)?;
         let certs = imds::get_certs()?;
 
@@ -37,8 +37,8 @@ impl Verifier {
         let Evidence { quote, report, .. } = evidence;
 
         let hcl_report = HclReport::new(report.clone())?;
-        let var_data_hash = hcl_report.var_data_sha256();
-        let ak_pub = hcl_report.ak_pub()?;
+        hcl_report.verify_binding()?;
+        let ak_pub = hcl_report.ak_pub_pkey()?;
         let snp_report: AttestationReport = hcl_report.try_into()?;
 
         let cert_chain = amd_kds::get_cert_chain()?;
@@ -48,12 +48,7 @@ impl Verifier {
         vcek.validate(&cert_chain)?;
         snp_report.validate(&vcek)?;
 
-        if var_data_hash != snp_report.report_data[..32] {
-            return Err("var_data_hash mismatch".into());
-        }
-        let der = ak_pub.key.try_to_der()?;
-        let pub_key = PKey::public_key_from_der(&der)?;
-        quote.verify(&pub_key, nonce)?;
+        quote.verify(&ak_pub, nonce)?;
 
         Ok(())
     }
@@ -5,7 +5,9 @@
 //!
 //!  # SNP Report Validation
 //!
-//!  The following code will retrieve an SNP report from the vTPM device, parse it, and validate it against the AMD certificate chain. Finally it will verify that a hash of a raw HCL report's Variable Data is equal to the `report_data` field in an embedded [Attestation Report](sev::firmware::guest::AttestationReport) structure.
+//!  The following code will retrieve an SNP report from the vTPM device, parse it, and validate it against the AMD certificate chain. It will also verify that a hash of a raw HCL report's Variable Data is equal to the `report_data` field in an embedded [Attestation Report](sev::firmware::guest::AttestationReport) structure.
+//!
+//!  A `vtpm::VtpmSession` performs the vTPM calls on a single TPM context. The free functions in `vtpm`, e.g. `vtpm::get_report()`, open a session with the vTPM device for each call.
 //!
 //!  #
 //!  ```no_run
@@ -14,9 +16,11 @@
 //!  use std::error::Error;
 //!
 //!  fn main() -> Result<(), Box<dyn Error>> {
-//!    let bytes = vtpm::get_report()?;
+//!    let mut vtpm_session = vtpm::VtpmSession::open_device()?;
+//!    let bytes = vtpm_session.get_report()?;
 //!    let hcl_report = hcl::HclReport::new(bytes)?;
-//!    let var_data_hash = hcl_report.var_data_sha256();
+//!    hcl_report.verify_binding()?;
+//!    vtpm_session.verify_ak_pub(&hcl_report)?;
 //!    let snp_report: AttestationReport = hcl_report.try_into()?;
 //!
 //!    let vcek = amd_kds::get_vcek(&snp_report)?;
@@ -26,9 +30,25 @@
 //!    vcek.validate(&cert_chain)?;
 //!    snp_report.validate(&vcek)?;
 //!
-//!    if var_data_hash != snp_report.report_data[..32] {
-//!      return Err("var_data_hash mismatch".into());
-//!    }
+//!    Ok(())
+//!  }
+//!  ```
+//!
+//!  # Other TPMs
+//!
+//!  A session can also be opened with another TCTI, e.g. to test an attestation flow against swtpm.
+//!
+//!  ```no_run
+//!  use az_snp_vtpm::vtpm::{TctiNameConf, VtpmSession};
+//!  use std::error::Error;
+//!  use std::str::FromStr;
+//!
+//!  fn main() -> Result<(), Box<dyn Error>> {
+//!    let conf = TctiNameConf::from_str("swtpm:host=localhost,port=2321")?;
+//!    let mut vtpm_session = VtpmSession::new(conf)?;
+//!    let nonce = "a nonce".as_bytes();
+//!    let quote = vtpm_session.get_quote(nonce)?;
+//!    println!("{:02X?}", quote.message());
 //!
 //!    Ok(())
 //!  }
@@ -50,6 +50,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 None => vtpm::get_report()?,
             };
             let hcl_report = HclReport::new(bytes)?;
+            hcl_report.verify_binding()?;
             let snp_report: AttestationReport = hcl_report.try_into()?;
 
             let (vcek, cert_chain) = if imds {
@@ -72,7 +73,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         }
         Action::Quote { nonce } => {
-            println!("quote byte size: {}", nonce.as_bytes().len());
+            println!("quote byte size: {}", nonce.len());
             let quote = vtpm::get_quote(nonce.as_bytes())?;
             println!("{:02X?}", quote.message());
         }
@@ -100,8 +100,6 @@ mod tests {
     fn test_report_data_hash() {
         let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
         let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
-        let var_data_hash = hcl_report.var_data_sha256();
-        let snp_report: AttestationReport = hcl_report.try_into().unwrap();
-        assert!(var_data_hash == snp_report.report_data[..32]);
+        hcl_report.verify_binding_zero_padded().unwrap();
     }
 }
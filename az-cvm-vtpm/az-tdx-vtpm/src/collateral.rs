@@ -0,0 +1,196 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// A Provisioning Certificate Caching Service (PCCS) caches Intel's collateral and serves it with
+// the same API as Intel's PCS, see "Intel SGX and TDX Provisioning Certification Service (PCS)
+// API", Version 4. Unlike the PCS, it looks up PCK certificates by QE ID.
+
+use crate::pcs::{
+    Collateral, PckCa, PckCertificate, PcsClient, PcsError, SignedQeIdentity, SignedTcbInfo,
+};
+use crate::quote::{TdQuote, VerifyError};
+use crate::tcb::{PckExtension, TcbError};
+use openssl::nid::Nid;
+use openssl::x509::X509;
+use thiserror::Error;
+
+const PCK_PLATFORM_CA_CN: &str = "Intel SGX PCK Platform CA";
+const PCK_PROCESSOR_CA_CN: &str = "Intel SGX PCK Processor CA";
+
+#[derive(Error, Debug)]
+pub enum CollateralError {
+    #[error("PCS error")]
+    Pcs(#[from] PcsError),
+    #[error("quote verification error")]
+    Verify(#[from] VerifyError),
+    #[error("TCB error")]
+    Tcb(#[from] TcbError),
+    #[error("unknown PCK CA")]
+    UnknownPckCa,
+}
+
+/// Client for a caching service of Intel's collateral, e.g. a PCCS.
+/// **Note:** there is no default endpoint, the cache's base URL has to be configured.
+pub struct PccsClient {
+    pcs: PcsClient,
+}
+
+impl PccsClient {
+    /// Create a client for a PCCS at the given base URL, e.g. a local mock server
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let pcs = PcsClient::new(base_url);
+        Self { pcs }
+    }
+
+    /// Retrieve a PCK certificate, based on the QE ID and the platform's TCB. All arguments
+    /// are hex-encoded.
+    pub fn get_pck_cert(
+        &self,
+        qeid: &str,
+        cpusvn: &str,
+        pcesvn: &str,
+        pceid: &str,
+    ) -> Result<PckCertificate, CollateralError> {
+        let query = format!("qeid={qeid}&cpusvn={cpusvn}&pcesvn={pcesvn}&pceid={pceid}");
+        let pck_cert = self.pcs.get_pck_cert_by_query(&query)?;
+        Ok(pck_cert)
+    }
+
+    /// Retrieve the TDX TCB Info of a platform, identified by its hex-encoded FMSPC
+    pub fn get_tcb_info(&self, fmspc: &str) -> Result<SignedTcbInfo, CollateralError> {
+        let tcb_info = self.pcs.get_tcb_info(fmspc)?;
+        Ok(tcb_info)
+    }
+
+    /// Retrieve the identity of the TDX Quoting Enclave
+    pub fn get_qe_identity(&self) -> Result<SignedQeIdentity, CollateralError> {
+        let qe_identity = self.pcs.get_qe_identity()?;
+        Ok(qe_identity)
+    }
+
+    /// Retrieve all collateral for a platform, identified by its hex-encoded FMSPC and the CA
+    /// that issued its PCK certificate
+    pub fn get_collateral(&self, fmspc: &str, ca: PckCa) -> Result<Collateral, CollateralError> {
+        let collateral = self.pcs.get_collateral(fmspc, ca)?;
+        Ok(collateral)
+    }
+
+    /// Retrieve all collateral that is needed to verify the given TD quote. The platform is
+    /// identified by the PCK certificate in the quote's certification data.
+    pub fn get_quote_collateral(&self, td_quote: &TdQuote) -> Result<Collateral, CollateralError> {
+        let pck = td_quote.pck_chain()?.pck;
+        let fmspc = hex::encode_upper(PckExtension::from_cert(&pck)?.fmspc);
+        let ca = pck_ca(&pck).ok_or(CollateralError::UnknownPckCa)?;
+        self.get_collateral(&fmspc, ca)
+    }
+}
+
+/// Determine the CA that issued a PCK certificate by the issuer's common name
+fn pck_ca(pck: &X509) -> Option<PckCa> {
+    let issuer_cn = pck.issuer_name().entries_by_nid(Nid::COMMONNAME).next()?;
+    match issuer_cn.data().as_slice() {
+        cn if cn == PCK_PLATFORM_CA_CN.as_bytes() => Some(PckCa::Platform),
+        cn if cn == PCK_PROCESSOR_CA_CN.as_bytes() => Some(PckCa::Processor),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcs::tests::{collateral, serve, url_encode, MockResponse};
+    use crate::quote::parse;
+
+    #[test]
+    fn get_pck_cert_from_pccs() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let pck_chain = td_quote.pck_chain().unwrap();
+        let issuer_chain = [
+            pck_chain.pck_ca.to_pem().unwrap(),
+            pck_chain.root_ca.to_pem().unwrap(),
+        ]
+        .concat();
+        let base_url = serve(vec![MockResponse {
+            path: "/sgx/certification/v4/pckcert?qeid=00&cpusvn=01&pcesvn=0b00&pceid=0000".into(),
+            headers: vec![
+                (
+                    "SGX-PCK-Certificate-Issuer-Chain",
+                    url_encode(&String::from_utf8(issuer_chain).unwrap().into()),
+                ),
+                ("SGX-TCBm", "01".into()),
+                ("SGX-FMSPC", "B0C06F000000".into()),
+            ],
+            body: pck_chain.pck.to_pem().unwrap(),
+        }]);
+        let client = PccsClient::new(base_url);
+
+        let pck_cert = client.get_pck_cert("00", "01", "0b00", "0000").unwrap();
+        assert_eq!(
+            pck_cert.cert.to_der().unwrap(),
+            pck_chain.pck.to_der().unwrap()
+        );
+        assert_eq!(pck_cert.issuer_chain.len(), 2);
+        assert_eq!(pck_cert.fmspc, "B0C06F000000");
+    }
+
+    #[test]
+    fn get_quote_collateral_from_pccs() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let pck = td_quote.pck_chain().unwrap().pck;
+        assert_eq!(pck_ca(&pck), Some(PckCa::Platform));
+
+        let collateral = collateral();
+        let response =
+            |path: &str, header: &'static str, chain: &str, body: Vec<u8>| MockResponse {
+                path: path.into(),
+                headers: vec![(header, url_encode(&collateral[chain]))],
+                body,
+            };
+        let tcb_info_body = format!(
+            r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+            collateral["tcb_info"].as_str().unwrap(),
+            collateral["tcb_info_signature"].as_str().unwrap()
+        );
+        let qe_identity_body = format!(
+            r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+            collateral["qe_identity"].as_str().unwrap(),
+            collateral["qe_identity_signature"].as_str().unwrap()
+        );
+        let crl = |name: &str| hex::decode(collateral[name].as_str().unwrap()).unwrap();
+        let base_url = serve(vec![
+            response(
+                "/tdx/certification/v4/tcb?fmspc=B0C06F000000",
+                "TCB-Info-Issuer-Chain",
+                "tcb_info_issuer_chain",
+                tcb_info_body.into_bytes(),
+            ),
+            response(
+                "/tdx/certification/v4/qe/identity",
+                "SGX-Enclave-Identity-Issuer-Chain",
+                "qe_identity_issuer_chain",
+                qe_identity_body.into_bytes(),
+            ),
+            response(
+                "/sgx/certification/v4/pckcrl?ca=platform&encoding=der",
+                "SGX-PCK-CRL-Issuer-Chain",
+                "pck_crl_issuer_chain",
+                crl("pck_crl"),
+            ),
+            MockResponse {
+                path: "/sgx/certification/v4/rootcacrl".into(),
+                headers: vec![],
+                body: crl("root_ca_crl"),
+            },
+        ]);
+        let client = PccsClient::new(base_url);
+
+        let collateral = client.get_quote_collateral(&td_quote).unwrap();
+        assert_eq!(collateral.tcb_info.tcb_info.fmspc, "B0C06F000000");
+        assert_eq!(collateral.qe_identity.qe_identity.id, "TD_QE");
+        collateral.tcb_info.verify(&collateral.root_ca_crl).unwrap();
+        collateral
+            .qe_identity
+            .verify(&collateral.root_ca_crl)
+            .unwrap();
+    }
+}
@@ -0,0 +1,406 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// Types are based on "TCG PC Client Platform Firmware Profile Specification", Version 1.05,
+// Section 10 (Event Logging) and "Intel TDX Guest-Hypervisor Communication Interface",
+// Section 4.4 (CC Event Log)
+
+use crate::quote::TdQuote;
+use crate::reader::{Reader, Truncated};
+use az_cvm_vtpm::tdx::{Rtmr, TdReport};
+use sha2::{Digest, Sha384};
+use thiserror::Error;
+
+const CCEL_TABLE_PATH: &str = "/sys/firmware/acpi/tables/CCEL";
+const CCEL_DATA_PATH: &str = "/sys/firmware/acpi/tables/data/CCEL";
+const CCEL_SIGNATURE: &[u8; 4] = b"CCEL";
+const CC_TYPE_TDX: u8 = 2;
+const ACPI_HEADER_SIZE: usize = 36;
+const SPEC_ID_EVENT_SIGNATURE: &[u8; 16] = b"Spec ID Event03\0";
+const SHA1_DIGEST_SIZE: usize = 20;
+const SHA384_DIGEST_SIZE: usize = 48;
+const TPM_ALG_SHA384: u16 = 0x000c;
+const EVENT_HEADER_SIZE: usize = 8;
+const RTMR_COUNT: usize = 4;
+pub const EV_NO_ACTION: u32 = 0x3;
+
+#[derive(Error, Debug)]
+pub enum EventLogError {
+    #[error("event log truncated, expected at least {0} more bytes, got {1}")]
+    Truncated(usize, usize),
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("invalid CCEL table")]
+    InvalidCcelTable,
+    #[error("unsupported CC type {0}")]
+    UnsupportedCcType(u8),
+    #[error("invalid Spec ID event")]
+    InvalidSpecIdEvent,
+    #[error("event log does not contain SHA-384 digests")]
+    MissingSha384,
+    #[error("unknown digest algorithm {0:#06x}")]
+    UnknownAlgorithm(u16),
+    #[error("invalid digest size {1} for algorithm {0:#06x}")]
+    InvalidDigestSize(u16, usize),
+    #[error("unexpected data after the last event")]
+    TrailingData,
+    #[error("RTMR{0} does not match the event log")]
+    RtmrMismatch(usize),
+}
+
+impl From<Truncated> for EventLogError {
+    fn from(Truncated(expected, remaining): Truncated) -> Self {
+        Self::Truncated(expected, remaining)
+    }
+}
+
+/// The ACPI CCEL table, which describes the location of the CC event log
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcelTable {
+    pub cc_type: u8,
+    pub cc_subtype: u8,
+    pub log_area_minimum_length: u64,
+    pub log_area_start_address: u64,
+}
+
+impl CcelTable {
+    /// Parse the raw ACPI CCEL table
+    pub fn parse(bytes: &[u8]) -> Result<Self, EventLogError> {
+        let mut reader = Reader::new(bytes);
+        let header = reader.take(ACPI_HEADER_SIZE)?;
+        if &header[..4] != CCEL_SIGNATURE {
+            return Err(EventLogError::InvalidCcelTable);
+        }
+        let cc_type = reader.u8()?;
+        if cc_type != CC_TYPE_TDX {
+            return Err(EventLogError::UnsupportedCcType(cc_type));
+        }
+        let cc_subtype = reader.u8()?;
+        let _reserved = reader.u16()?;
+        let log_area_minimum_length = reader.u64()?;
+        let log_area_start_address = reader.u64()?;
+        Ok(Self {
+            cc_type,
+            cc_subtype,
+            log_area_minimum_length,
+            log_area_start_address,
+        })
+    }
+}
+
+/// A measured event of the CC event log
+#[derive(Clone, Debug, PartialEq)]
+pub struct CcEvent {
+    /// Index of the measurement register: 0 for MRTD, 1-4 for RTMR0-3
+    pub mr_index: u32,
+    pub event_type: u32,
+    /// SHA-384 digest that has been extended into the measurement register
+    pub digest: [u8; 48],
+    pub event_data: Vec<u8>,
+}
+
+impl CcEvent {
+    /// Index of the RTMR this event has been extended into, if any
+    pub fn rtmr_index(&self) -> Option<usize> {
+        match self.mr_index as usize {
+            index @ 1..=RTMR_COUNT if self.event_type != EV_NO_ACTION => Some(index - 1),
+            _ => None,
+        }
+    }
+}
+
+/// A CC event log, with the events in the order in which they have been measured
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventLog {
+    pub events: Vec<CcEvent>,
+}
+
+impl EventLog {
+    /// Events that have been extended into the given RTMR
+    pub fn rtmr_events(&self, rtmr_index: usize) -> impl Iterator<Item = &CcEvent> {
+        self.events
+            .iter()
+            .filter(move |event| event.rtmr_index() == Some(rtmr_index))
+    }
+
+    /// Replay the SHA-384 extends of the event log into RTMR0-3
+    pub fn replay(&self) -> [Rtmr; 4] {
+        let mut rtmrs = [Rtmr {
+            register_data: [0; 48],
+        }; RTMR_COUNT];
+        for event in &self.events {
+            let Some(index) = event.rtmr_index() else {
+                continue;
+            };
+            let mut hasher = Sha384::new();
+            hasher.update(rtmrs[index].register_data);
+            hasher.update(event.digest);
+            rtmrs[index].register_data = hasher.finalize().into();
+        }
+        rtmrs
+    }
+
+    /// Check that replaying the event log yields the given RTMRs
+    pub fn verify_rtmrs(&self, rtmrs: &[Rtmr; 4]) -> Result<(), EventLogError> {
+        let replayed = self.replay();
+        for (index, (replayed, rtmr)) in replayed.iter().zip(rtmrs).enumerate() {
+            if replayed != rtmr {
+                return Err(EventLogError::RtmrMismatch(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the event log against the RTMRs of a TdReport
+    pub fn verify_td_report(&self, td_report: &TdReport) -> Result<(), EventLogError> {
+        self.verify_rtmrs(&td_report.tdinfo.rtrm)
+    }
+
+    /// Check the event log against the RTMRs of a TD quote
+    pub fn verify_quote(&self, td_quote: &TdQuote) -> Result<(), EventLogError> {
+        self.verify_rtmrs(&td_quote.body.rtmr)
+    }
+}
+
+/// Parse the Spec ID event and return the digest sizes of the log's algorithms
+fn parse_spec_id_event(reader: &mut Reader) -> Result<Vec<(u16, usize)>, EventLogError> {
+    let _mr_index = reader.u32()?;
+    let event_type = reader.u32()?;
+    let _digest = reader.take(SHA1_DIGEST_SIZE)?;
+    let event_size = reader.u32()? as usize;
+    if event_type != EV_NO_ACTION {
+        return Err(EventLogError::InvalidSpecIdEvent);
+    }
+
+    let mut event = Reader::new(reader.take(event_size)?);
+    if event.take(SPEC_ID_EVENT_SIGNATURE.len())? != SPEC_ID_EVENT_SIGNATURE {
+        return Err(EventLogError::InvalidSpecIdEvent);
+    }
+    let _platform_class = event.u32()?;
+    let _spec_version = event.take(3)?;
+    let _uintn_size = event.u8()?;
+    let number_of_algorithms = event.u32()?;
+    let mut algorithms: Vec<(u16, usize)> = vec![];
+    for _ in 0..number_of_algorithms {
+        let algorithm_id = event.u16()?;
+        let digest_size = event.u16()? as usize;
+        if algorithms.iter().any(|&(id, _)| id == algorithm_id) {
+            return Err(EventLogError::InvalidSpecIdEvent);
+        }
+        if algorithm_id == TPM_ALG_SHA384 && digest_size != SHA384_DIGEST_SIZE {
+            return Err(EventLogError::InvalidDigestSize(algorithm_id, digest_size));
+        }
+        algorithms.push((algorithm_id, digest_size));
+    }
+    if !algorithms.iter().any(|&(id, _)| id == TPM_ALG_SHA384) {
+        return Err(EventLogError::MissingSha384);
+    }
+    Ok(algorithms)
+}
+
+fn parse_event(reader: &mut Reader, algorithms: &[(u16, usize)]) -> Result<CcEvent, EventLogError> {
+    let mr_index = reader.u32()?;
+    let event_type = reader.u32()?;
+    let digest_count = reader.u32()?;
+    let mut digest = None;
+    for _ in 0..digest_count {
+        let algorithm_id = reader.u16()?;
+        let digest_size = algorithms
+            .iter()
+            .find_map(|&(id, size)| (id == algorithm_id).then_some(size))
+            .ok_or(EventLogError::UnknownAlgorithm(algorithm_id))?;
+        let bytes = reader.take(digest_size)?;
+        if algorithm_id == TPM_ALG_SHA384 {
+            let sha384 = bytes
+                .try_into()
+                .map_err(|_| EventLogError::InvalidDigestSize(algorithm_id, digest_size))?;
+            digest = Some(sha384);
+        }
+    }
+    let digest = digest.ok_or(EventLogError::MissingSha384)?;
+    let event_size = reader.u32()? as usize;
+    let event_data = reader.take(event_size)?.to_vec();
+    Ok(CcEvent {
+        mr_index,
+        event_type,
+        digest,
+        event_data,
+    })
+}
+
+/// Whether the bytes mark unused space in the log area, which is filled with 0x00 or 0xff
+fn is_unused(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| b == 0) || bytes.iter().all(|&b| b == 0xff)
+}
+
+/// Parse a TCG2 crypto-agile event log, as found in the CCEL log area. The log ends at the first
+/// event header that is filled with 0x00 or 0xff, the unused space after it is ignored.
+pub fn parse(bytes: &[u8]) -> Result<EventLog, EventLogError> {
+    let mut reader = Reader::new(bytes);
+    let algorithms = parse_spec_id_event(&mut reader)?;
+    let mut events = vec![];
+    while let Some(header) = reader.bytes.get(..EVENT_HEADER_SIZE) {
+        if is_unused(header) {
+            break;
+        }
+        events.push(parse_event(&mut reader, &algorithms)?);
+    }
+    if !reader.bytes.iter().all(|&b| b == 0 || b == 0xff) {
+        return Err(EventLogError::TrailingData);
+    }
+    Ok(EventLog { events })
+}
+
+/// Read the CC event log from the ACPI CCEL table and parse it
+pub fn get_event_log() -> Result<EventLog, EventLogError> {
+    let table = std::fs::read(CCEL_TABLE_PATH)?;
+    CcelTable::parse(&table)?;
+    let bytes = std::fs::read(CCEL_DATA_PATH)?;
+    parse(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use az_cvm_vtpm::hcl::HclReport;
+
+    const TPM_ALG_SHA256: u16 = 0x000b;
+    const EV_EFI_ACTION: u32 = 0x80000007;
+
+    fn spec_id_event() -> Vec<u8> {
+        let mut event = SPEC_ID_EVENT_SIGNATURE.to_vec();
+        event.extend(0u32.to_le_bytes());
+        event.extend([0, 2, 0, 2]);
+        event.extend(2u32.to_le_bytes());
+        event.extend(TPM_ALG_SHA256.to_le_bytes());
+        event.extend(32u16.to_le_bytes());
+        event.extend(TPM_ALG_SHA384.to_le_bytes());
+        event.extend(48u16.to_le_bytes());
+        event.push(0);
+
+        let mut bytes = vec![];
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(EV_NO_ACTION.to_le_bytes());
+        bytes.extend([0; SHA1_DIGEST_SIZE]);
+        bytes.extend((event.len() as u32).to_le_bytes());
+        bytes.extend(event);
+        bytes
+    }
+
+    fn event(mr_index: u32, event_type: u32, digest: [u8; 48], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(mr_index.to_le_bytes());
+        bytes.extend(event_type.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(TPM_ALG_SHA256.to_le_bytes());
+        bytes.extend([0xaa; 32]);
+        bytes.extend(TPM_ALG_SHA384.to_le_bytes());
+        bytes.extend(digest);
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn event_log() -> Vec<u8> {
+        let mut bytes = spec_id_event();
+        bytes.extend(event(1, EV_EFI_ACTION, [1; 48], b"firmware"));
+        bytes.extend(event(1, EV_NO_ACTION, [9; 48], b"ignored"));
+        bytes.extend(event(2, EV_EFI_ACTION, [2; 48], b"bootloader"));
+        bytes.extend(event(1, EV_EFI_ACTION, [3; 48], b"config"));
+        bytes.resize(bytes.len() + 64, 0xff);
+        bytes
+    }
+
+    fn extend(rtmr: [u8; 48], digest: [u8; 48]) -> [u8; 48] {
+        Sha384::digest([rtmr, digest].concat()).into()
+    }
+
+    #[test]
+    fn parse_event_log() {
+        let event_log = parse(&event_log()).unwrap();
+        assert_eq!(event_log.events.len(), 4);
+        assert_eq!(event_log.events[2].event_data, b"bootloader");
+        assert_eq!(event_log.rtmr_events(0).count(), 2);
+        assert_eq!(event_log.rtmr_events(1).count(), 1);
+
+        let rtmrs = event_log.replay();
+        let rtmr0 = extend(extend([0; 48], [1; 48]), [3; 48]);
+        assert_eq!(rtmrs[0].register_data, rtmr0);
+        assert_eq!(rtmrs[1].register_data, extend([0; 48], [2; 48]));
+        assert_eq!(rtmrs[2].register_data, [0; 48]);
+    }
+
+    #[test]
+    fn verify_td_report() {
+        let event_log = parse(&event_log()).unwrap();
+        let bytes = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let mut td_report: TdReport = hcl_report.try_into().unwrap();
+        td_report.tdinfo.rtrm = event_log.replay();
+        event_log.verify_td_report(&td_report).unwrap();
+
+        td_report.tdinfo.rtrm[1].register_data[0] ^= 1;
+        assert!(matches!(
+            event_log.verify_td_report(&td_report),
+            Err(EventLogError::RtmrMismatch(1))
+        ));
+    }
+
+    #[test]
+    fn parse_ccel_capture() {
+        let bytes = include_bytes!("../test/ccel-eventlog.bin");
+        let event_log = parse(bytes).unwrap();
+        assert_eq!(event_log.events.len(), 1);
+        let event = &event_log.events[0];
+        assert_eq!(event.rtmr_index(), Some(0));
+        assert_eq!(event.event_type, 0x8000000b);
+        assert_eq!(&event.event_data[1..9], b"TdxTable");
+
+        let rtmrs = event_log.replay();
+        assert_eq!(rtmrs[0].register_data, extend([0; 48], event.digest));
+        assert_eq!(rtmrs[1].register_data, [0; 48]);
+    }
+
+    #[test]
+    fn reject_malformed_event_logs() {
+        let mut duplicate = spec_id_event();
+        let algorithms_offset = duplicate.len() - 9;
+        duplicate[algorithms_offset..algorithms_offset + 2]
+            .copy_from_slice(&TPM_ALG_SHA384.to_le_bytes());
+        assert!(matches!(
+            parse(&duplicate),
+            Err(EventLogError::InvalidDigestSize(TPM_ALG_SHA384, 32))
+        ));
+        duplicate[algorithms_offset + 2..algorithms_offset + 4]
+            .copy_from_slice(&48u16.to_le_bytes());
+        assert!(matches!(
+            parse(&duplicate),
+            Err(EventLogError::InvalidSpecIdEvent)
+        ));
+
+        let mut trailing_data = event_log();
+        trailing_data.extend([0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert!(matches!(
+            parse(&trailing_data),
+            Err(EventLogError::TrailingData)
+        ));
+    }
+
+    #[test]
+    fn parse_ccel_table() {
+        let mut bytes = b"CCEL".to_vec();
+        bytes.resize(ACPI_HEADER_SIZE, 0);
+        bytes.extend([CC_TYPE_TDX, 0, 0, 0]);
+        bytes.extend(0x10000u64.to_le_bytes());
+        bytes.extend(0x7f000000u64.to_le_bytes());
+        let table = CcelTable::parse(&bytes).unwrap();
+        assert_eq!(table.log_area_minimum_length, 0x10000);
+        assert_eq!(table.log_area_start_address, 0x7f000000);
+
+        bytes[ACPI_HEADER_SIZE] = 1;
+        assert!(matches!(
+            CcelTable::parse(&bytes),
+            Err(EventLogError::UnsupportedCcType(1))
+        ));
+    }
+}
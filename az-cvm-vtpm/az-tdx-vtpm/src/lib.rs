@@ -12,38 +12,63 @@
 //!  Key (AK). A hash of the Variable Data block is included in the TD report as `reportdata`.
 //!  TPM quotes retrieved with `vtpm::get_quote()` should be signed by this AK. A verification
 //!  function would need to check this to ensure the TD report is linked to this unique TDX CVM.
+//!  A TD quote is verified against Intel's root of trust with `TdQuote::verify_with_crls()`,
+//!  using the CRLs of the collateral from Intel's PCS, see `pcs::PcsClient`.
+//!  A `vtpm::VtpmSession` performs these vTPM calls on a single TPM context, which can also be
+//!  opened with another TCTI, e.g. swtpm.
 //!  
 //!  #
 //!  ```no_run
-//!  use az_tdx_vtpm::{hcl, imds, report, tdx, vtpm};
-//!  use openssl::pkey::{PKey, Public};
+//!  use az_tdx_vtpm::{hcl, imds, pcs, quote, report, tcb, tdx, vtpm};
 //!  use std::error::Error;
 //!
 //!  fn main() -> Result<(), Box<dyn Error>> {
 //!    let td_report = report::get_report()?;
 //!    let td_quote_bytes = imds::get_td_quote(&td_report)?;
+//!    let td_quote = quote::parse(&td_quote_bytes)?;
+//!    assert!(td_quote.matches_td_report(&td_report));
+//!
+//!    let pck = td_quote.pck_chain()?.pck;
+//!    let fmspc = hex::encode_upper(tcb::PckExtension::from_cert(&pck)?.fmspc);
+//!    let collateral = pcs::PcsClient::default().get_collateral(&fmspc, pcs::PckCa::Platform)?;
+//!    td_quote.verify_with_crls(&collateral.pck_crl, &collateral.root_ca_crl)?;
+//!    let evaluation = tcb::evaluate(
+//!      &td_quote,
+//!      &collateral.tcb_info,
+//!      &collateral.qe_identity,
+//!      &collateral.root_ca_crl,
+//!    )?;
+//!    tcb::TcbPolicy::default().check(&evaluation)?;
+//!    td_quote.check_policy(&tdx::TdPolicy::default())?;
 //!    std::fs::write("td_quote.bin", td_quote_bytes)?;
 //!
-//!    let bytes = vtpm::get_report()?;
+//!    let mut vtpm_session = vtpm::VtpmSession::open_device()?;
+//!    let bytes = vtpm_session.get_report()?;
 //!    let hcl_report = hcl::HclReport::new(bytes)?;
-//!    let var_data_hash = hcl_report.var_data_sha256();
-//!    let ak_pub = hcl_report.ak_pub()?;
+//!    hcl_report.verify_binding()?;
+//!    vtpm_session.verify_ak_pub(&hcl_report)?;
+//!    let ak_pub = hcl_report.ak_pub_pkey()?;
 //!
-//!    let td_report: tdx::TdReport = hcl_report.try_into()?;
-//!    assert!(var_data_hash == td_report.report_mac.reportdata[..32]);
 //!    let nonce = "a nonce".as_bytes();
 //!
-//!    let tpm_quote = vtpm::get_quote(nonce)?;
-//!    let der = ak_pub.key.try_to_der()?;
-//!    let pub_key = PKey::public_key_from_der(&der)?;
-//!    tpm_quote.verify(&pub_key, nonce)?;
+//!    let tpm_quote = vtpm_session.get_quote(nonce)?;
+//!    tpm_quote.verify(&ak_pub, nonce)?;
 //!
 //!    Ok(())
 //!  }
 //!  ```
 
+#[cfg(feature = "verifier")]
+pub mod collateral;
+pub mod eventlog;
 pub mod imds;
+#[cfg(feature = "verifier")]
+pub mod pcs;
+pub mod quote;
+mod reader;
 pub mod report;
+#[cfg(feature = "verifier")]
+pub mod tcb;
 pub use az_cvm_vtpm::{hcl, tdx, vtpm};
 
 /// Determines if the current VM is a TDX CVM.
@@ -62,14 +87,11 @@ pub fn is_tdx_cvm() -> Result<bool, vtpm::ReportError> {
 mod tests {
     use super::*;
     use hcl::HclReport;
-    use tdx::TdReport;
 
     #[test]
     fn test_report_data_hash() {
         let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
         let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
-        let var_data_hash = hcl_report.var_data_sha256();
-        let td_report: TdReport = hcl_report.try_into().unwrap();
-        assert!(var_data_hash == td_report.report_mac.reportdata[..32]);
+        hcl_report.verify_binding_zero_padded().unwrap();
     }
 }
@@ -0,0 +1,647 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// Endpoints are based on "Intel SGX and TDX Provisioning Certification Service (PCS) API",
+// Version 4
+
+use openssl::x509::{X509Crl, X509CrlRef, X509};
+use percent_encoding::percent_decode;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::fmt;
+use std::io::Read;
+use thiserror::Error;
+
+const PCS_SITE: &str = "https://api.trustedservices.intel.com";
+const PCS_SGX_CERTIFICATION: &str = "/sgx/certification/v4";
+const PCS_TDX_CERTIFICATION: &str = "/tdx/certification/v4";
+const PCS_API_KEY_HEADER: &str = "Ocp-Apim-Subscription-Key";
+const PCK_CERT_ISSUER_CHAIN_HEADER: &str = "SGX-PCK-Certificate-Issuer-Chain";
+const PCK_CRL_ISSUER_CHAIN_HEADER: &str = "SGX-PCK-CRL-Issuer-Chain";
+const TCB_INFO_ISSUER_CHAIN_HEADER: &str = "TCB-Info-Issuer-Chain";
+const QE_IDENTITY_ISSUER_CHAIN_HEADER: &str = "SGX-Enclave-Identity-Issuer-Chain";
+const TCBM_HEADER: &str = "SGX-TCBm";
+const FMSPC_HEADER: &str = "SGX-FMSPC";
+
+#[derive(Error, Debug)]
+pub enum PcsError {
+    #[error("http error")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("openssl error")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+    #[error("json error")]
+    Json(#[from] serde_json::Error),
+    #[error("hex error")]
+    Hex(#[from] hex::FromHexError),
+    #[error("missing header {0}")]
+    MissingHeader(&'static str),
+    #[error("invalid header {0}")]
+    InvalidHeader(&'static str),
+}
+
+/// Status of a TCB level, as found in TCB Info and QE Identity
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum TcbStatus {
+    UpToDate,
+    SWHardeningNeeded,
+    ConfigurationNeeded,
+    ConfigurationAndSWHardeningNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct TcbComponent {
+    pub svn: u8,
+    pub category: Option<String>,
+    #[serde(rename = "type")]
+    pub component_type: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Tcb {
+    pub sgxtcbcomponents: Vec<TcbComponent>,
+    pub pcesvn: u16,
+    #[serde(default)]
+    pub tdxtcbcomponents: Vec<TcbComponent>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbLevel {
+    pub tcb: Tcb,
+    pub tcb_date: String,
+    pub tcb_status: TcbStatus,
+    #[serde(rename = "advisoryIDs", default)]
+    pub advisory_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct IsvSvn {
+    pub isvsvn: u16,
+}
+
+/// TCB level of an enclave or a TDX module, identified by its ISV SVN
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IsvTcbLevel {
+    pub tcb: IsvSvn,
+    pub tcb_date: String,
+    pub tcb_status: TcbStatus,
+    #[serde(rename = "advisoryIDs", default)]
+    pub advisory_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdxModule {
+    #[serde(with = "hex::serde")]
+    pub mrsigner: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub attributes: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub attributes_mask: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdxModuleIdentity {
+    pub id: String,
+    #[serde(with = "hex::serde")]
+    pub mrsigner: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub attributes: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub attributes_mask: Vec<u8>,
+    pub tcb_levels: Vec<IsvTcbLevel>,
+}
+
+/// TCB Info of a platform, identified by its FMSPC
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TcbInfo {
+    pub id: String,
+    pub version: u32,
+    pub issue_date: String,
+    pub next_update: String,
+    pub fmspc: String,
+    pub pce_id: String,
+    pub tcb_type: u32,
+    pub tcb_evaluation_data_number: u32,
+    pub tdx_module: Option<TdxModule>,
+    #[serde(default)]
+    pub tdx_module_identities: Vec<TdxModuleIdentity>,
+    pub tcb_levels: Vec<TcbLevel>,
+}
+
+/// Identity of the Quoting Enclave
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QeIdentity {
+    pub id: String,
+    pub version: u32,
+    pub issue_date: String,
+    pub next_update: String,
+    pub tcb_evaluation_data_number: u32,
+    #[serde(with = "hex::serde")]
+    pub miscselect: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub miscselect_mask: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub attributes: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub attributes_mask: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub mrsigner: Vec<u8>,
+    pub isvprodid: u16,
+    pub tcb_levels: Vec<IsvTcbLevel>,
+}
+
+#[derive(Deserialize)]
+struct TcbInfoResponse<'a> {
+    #[serde(rename = "tcbInfo", borrow)]
+    tcb_info: &'a RawValue,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct QeIdentityResponse<'a> {
+    #[serde(rename = "enclaveIdentity", borrow)]
+    qe_identity: &'a RawValue,
+    signature: String,
+}
+
+/// TCB Info, with the signed JSON, its signature and the issuer chain
+#[derive(Clone, Debug)]
+pub struct SignedTcbInfo {
+    pub tcb_info: TcbInfo,
+    pub tcb_info_json: String,
+    pub signature: Vec<u8>,
+    pub issuer_chain: Vec<X509>,
+}
+
+impl SignedTcbInfo {
+    /// Parse a TCB Info response body and the PEM-encoded issuer chain
+    pub fn parse(body: &[u8], issuer_chain: &[u8]) -> Result<Self, PcsError> {
+        let response: TcbInfoResponse = serde_json::from_slice(body)?;
+        let tcb_info_json = response.tcb_info.get().to_string();
+        let signed_tcb_info = Self {
+            tcb_info: serde_json::from_str(&tcb_info_json)?,
+            tcb_info_json,
+            signature: hex::decode(response.signature)?,
+            issuer_chain: X509::stack_from_pem(issuer_chain)?,
+        };
+        Ok(signed_tcb_info)
+    }
+}
+
+/// QE Identity, with the signed JSON, its signature and the issuer chain
+#[derive(Clone, Debug)]
+pub struct SignedQeIdentity {
+    pub qe_identity: QeIdentity,
+    pub qe_identity_json: String,
+    pub signature: Vec<u8>,
+    pub issuer_chain: Vec<X509>,
+}
+
+impl SignedQeIdentity {
+    /// Parse a QE Identity response body and the PEM-encoded issuer chain
+    pub fn parse(body: &[u8], issuer_chain: &[u8]) -> Result<Self, PcsError> {
+        let response: QeIdentityResponse = serde_json::from_slice(body)?;
+        let qe_identity_json = response.qe_identity.get().to_string();
+        let signed_qe_identity = Self {
+            qe_identity: serde_json::from_str(&qe_identity_json)?,
+            qe_identity_json,
+            signature: hex::decode(response.signature)?,
+            issuer_chain: X509::stack_from_pem(issuer_chain)?,
+        };
+        Ok(signed_qe_identity)
+    }
+}
+
+/// A PCK certificate, with its issuer chain and the platform's TCB
+#[derive(Clone, Debug)]
+pub struct PckCertificate {
+    pub cert: X509,
+    pub issuer_chain: Vec<X509>,
+    /// Hex-encoded CPUSVN and PCESVN of the certificate
+    pub tcbm: String,
+    pub fmspc: String,
+}
+
+/// A CRL and its issuer chain
+pub struct Crl {
+    pub crl: X509Crl,
+    pub issuer_chain: Vec<X509>,
+}
+
+impl fmt::Debug for Crl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Crl")
+            .field("crl", &DebugCrl(&self.crl))
+            .field("issuer_chain", &self.issuer_chain)
+            .finish()
+    }
+}
+
+/// Debug representation of a CRL, which openssl does not provide
+struct DebugCrl<'a>(&'a X509CrlRef);
+
+impl fmt::Debug for DebugCrl<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let revoked = self.0.get_revoked().map_or(0, |revoked| revoked.len());
+        f.debug_struct("X509Crl")
+            .field("issuer", &self.0.issuer_name())
+            .field("last_update", &self.0.last_update())
+            .field("next_update", &self.0.next_update())
+            .field("revoked", &revoked)
+            .finish()
+    }
+}
+
+/// The CA that issued a PCK certificate
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PckCa {
+    Platform,
+    Processor,
+}
+
+impl PckCa {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            PckCa::Platform => "platform",
+            PckCa::Processor => "processor",
+        }
+    }
+}
+
+/// All collateral that is needed to verify a TD quote of a given platform
+pub struct Collateral {
+    pub tcb_info: SignedTcbInfo,
+    pub qe_identity: SignedQeIdentity,
+    pub pck_crl: Crl,
+    pub root_ca_crl: X509Crl,
+}
+
+impl fmt::Debug for Collateral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Collateral")
+            .field("tcb_info", &self.tcb_info)
+            .field("qe_identity", &self.qe_identity)
+            .field("pck_crl", &self.pck_crl)
+            .field("root_ca_crl", &DebugCrl(&self.root_ca_crl))
+            .finish()
+    }
+}
+
+/// Client for Intel's Provisioning Certification Service, or a service with a compatible API
+pub struct PcsClient {
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl Default for PcsClient {
+    fn default() -> Self {
+        Self::new(PCS_SITE)
+    }
+}
+
+impl PcsClient {
+    /// Create a client for a PCS at the given base URL, e.g. a local mock server
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            api_key: None,
+        }
+    }
+
+    /// Set the API key, which Intel's PCS requires to retrieve PCK certificates
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    fn get(&self, path: &str) -> Result<ureq::Response, PcsError> {
+        let url = format!("{}{path}", self.base_url);
+        let mut request = ureq::get(&url);
+        if let Some(api_key) = &self.api_key {
+            request = request.set(PCS_API_KEY_HEADER, api_key);
+        }
+        let response = request.call().map_err(Box::new)?;
+        Ok(response)
+    }
+
+    /// Retrieve a PCK certificate, based on the platform's encrypted PPID and TCB. All
+    /// arguments are hex-encoded.
+    pub fn get_pck_cert(
+        &self,
+        encrypted_ppid: &str,
+        cpusvn: &str,
+        pcesvn: &str,
+        pceid: &str,
+    ) -> Result<PckCertificate, PcsError> {
+        let query = format!(
+            "encrypted_ppid={encrypted_ppid}&cpusvn={cpusvn}&pcesvn={pcesvn}&pceid={pceid}"
+        );
+        self.get_pck_cert_by_query(&query)
+    }
+
+    /// Retrieve a PCK certificate with a service-specific query
+    pub(crate) fn get_pck_cert_by_query(&self, query: &str) -> Result<PckCertificate, PcsError> {
+        let path = format!("{PCS_SGX_CERTIFICATION}/pckcert?{query}");
+        let response = self.get(&path)?;
+        let issuer_chain = issuer_chain(&response, PCK_CERT_ISSUER_CHAIN_HEADER)?;
+        let tcbm = header(&response, TCBM_HEADER)?.to_string();
+        let fmspc = header(&response, FMSPC_HEADER)?.to_string();
+        let cert = X509::from_pem(&read_body(response)?)?;
+        Ok(PckCertificate {
+            cert,
+            issuer_chain,
+            tcbm,
+            fmspc,
+        })
+    }
+
+    /// Retrieve the TDX TCB Info of a platform, identified by its hex-encoded FMSPC
+    pub fn get_tcb_info(&self, fmspc: &str) -> Result<SignedTcbInfo, PcsError> {
+        let path = format!("{PCS_TDX_CERTIFICATION}/tcb?fmspc={fmspc}");
+        let response = self.get(&path)?;
+        let issuer_chain = decoded_header(&response, TCB_INFO_ISSUER_CHAIN_HEADER)?;
+        SignedTcbInfo::parse(&read_body(response)?, &issuer_chain)
+    }
+
+    /// Retrieve the identity of the TDX Quoting Enclave
+    pub fn get_qe_identity(&self) -> Result<SignedQeIdentity, PcsError> {
+        let path = format!("{PCS_TDX_CERTIFICATION}/qe/identity");
+        let response = self.get(&path)?;
+        let issuer_chain = decoded_header(&response, QE_IDENTITY_ISSUER_CHAIN_HEADER)?;
+        SignedQeIdentity::parse(&read_body(response)?, &issuer_chain)
+    }
+
+    /// Retrieve the CRL of PCK certificates that have been issued by the given CA
+    pub fn get_pck_crl(&self, ca: PckCa) -> Result<Crl, PcsError> {
+        let path = format!(
+            "{PCS_SGX_CERTIFICATION}/pckcrl?ca={}&encoding=der",
+            ca.as_str()
+        );
+        let response = self.get(&path)?;
+        let issuer_chain = issuer_chain(&response, PCK_CRL_ISSUER_CHAIN_HEADER)?;
+        let crl = X509Crl::from_der(&read_body(response)?)?;
+        Ok(Crl { crl, issuer_chain })
+    }
+
+    /// Retrieve the CRL of the Intel SGX Root CA
+    pub fn get_root_ca_crl(&self) -> Result<X509Crl, PcsError> {
+        let path = format!("{PCS_SGX_CERTIFICATION}/rootcacrl");
+        let response = self.get(&path)?;
+        let crl = X509Crl::from_der(&read_body(response)?)?;
+        Ok(crl)
+    }
+
+    /// Retrieve all collateral for a platform, identified by its hex-encoded FMSPC and the CA
+    /// that issued its PCK certificate
+    pub fn get_collateral(&self, fmspc: &str, ca: PckCa) -> Result<Collateral, PcsError> {
+        let collateral = Collateral {
+            tcb_info: self.get_tcb_info(fmspc)?,
+            qe_identity: self.get_qe_identity()?,
+            pck_crl: self.get_pck_crl(ca)?,
+            root_ca_crl: self.get_root_ca_crl()?,
+        };
+        Ok(collateral)
+    }
+}
+
+fn read_body(response: ureq::Response) -> Result<Vec<u8>, PcsError> {
+    let mut buffer = Vec::new();
+    response.into_reader().read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn header<'a>(response: &'a ureq::Response, name: &'static str) -> Result<&'a str, PcsError> {
+    response.header(name).ok_or(PcsError::MissingHeader(name))
+}
+
+/// Issuer chains are sent as URL-encoded PEM in the response headers
+fn decoded_header(response: &ureq::Response, name: &'static str) -> Result<Vec<u8>, PcsError> {
+    let value = header(response, name)?;
+    Ok(percent_decode(value.as_bytes()).collect())
+}
+
+fn issuer_chain(response: &ureq::Response, name: &'static str) -> Result<Vec<X509>, PcsError> {
+    let pem = decoded_header(response, name)?;
+    let certs = X509::stack_from_pem(&pem).map_err(|_| PcsError::InvalidHeader(name))?;
+    Ok(certs)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::quote::parse;
+    use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+    use serde_json::Value;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    pub(crate) struct MockResponse {
+        pub path: String,
+        pub headers: Vec<(&'static str, String)>,
+        pub body: Vec<u8>,
+    }
+
+    /// Serve the given responses, one per request, and return the server's base URL
+    pub(crate) fn serve(responses: Vec<MockResponse>) -> String {
+        serve_requiring_header(None, responses)
+    }
+
+    /// Serve the given responses, like `serve()`, but check that each request has the given
+    /// header
+    pub(crate) fn serve_requiring_header(
+        required_header: Option<&'static str>,
+        responses: Vec<MockResponse>,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut request_headers = vec![];
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    request_headers.push(line.trim_end().to_string());
+                    line.clear();
+                }
+                assert!(request_line.starts_with(&format!("GET {} ", response.path)));
+                if let Some(required_header) = required_header {
+                    assert!(request_headers.iter().any(|h| h == required_header));
+                }
+
+                let mut head = format!(
+                    "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: {}\r\n",
+                    response.body.len()
+                );
+                for (name, value) in response.headers {
+                    head.push_str(&format!("{name}: {value}\r\n"));
+                }
+                head.push_str("\r\n");
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&response.body).unwrap();
+            }
+        });
+        base_url
+    }
+
+    pub(crate) fn collateral() -> Value {
+        let bytes = include_bytes!("../test/tdx-collateral.json");
+        serde_json::from_slice(bytes).unwrap()
+    }
+
+    pub(crate) fn url_encode(value: &Value) -> String {
+        utf8_percent_encode(value.as_str().unwrap(), NON_ALPHANUMERIC).to_string()
+    }
+
+    #[test]
+    fn get_tcb_info_and_qe_identity() {
+        let collateral = collateral();
+        let tcb_info_body = format!(
+            r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+            collateral["tcb_info"].as_str().unwrap(),
+            collateral["tcb_info_signature"].as_str().unwrap()
+        );
+        let qe_identity_body = format!(
+            r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+            collateral["qe_identity"].as_str().unwrap(),
+            collateral["qe_identity_signature"].as_str().unwrap()
+        );
+        let base_url = serve(vec![
+            MockResponse {
+                path: "/tdx/certification/v4/tcb?fmspc=B0C06F000000".into(),
+                headers: vec![(
+                    TCB_INFO_ISSUER_CHAIN_HEADER,
+                    url_encode(&collateral["tcb_info_issuer_chain"]),
+                )],
+                body: tcb_info_body.into_bytes(),
+            },
+            MockResponse {
+                path: "/tdx/certification/v4/qe/identity".into(),
+                headers: vec![(
+                    QE_IDENTITY_ISSUER_CHAIN_HEADER,
+                    url_encode(&collateral["qe_identity_issuer_chain"]),
+                )],
+                body: qe_identity_body.into_bytes(),
+            },
+        ]);
+        let client = PcsClient::new(base_url);
+
+        let tcb_info = client.get_tcb_info("B0C06F000000").unwrap();
+        assert_eq!(tcb_info.tcb_info_json, collateral["tcb_info"]);
+        assert_eq!(tcb_info.signature.len(), 64);
+        assert_eq!(tcb_info.issuer_chain.len(), 2);
+        assert_eq!(tcb_info.tcb_info.id, "TDX");
+        assert_eq!(tcb_info.tcb_info.fmspc, "B0C06F000000");
+        assert_eq!(tcb_info.tcb_info.tdx_module_identities.len(), 2);
+        let tcb_level = &tcb_info.tcb_info.tcb_levels[0];
+        assert_eq!(tcb_level.tcb_status, TcbStatus::UpToDate);
+        assert_eq!(tcb_level.tcb.sgxtcbcomponents.len(), 16);
+        assert_eq!(tcb_level.tcb.tdxtcbcomponents[0].svn, 5);
+
+        let qe_identity = client.get_qe_identity().unwrap();
+        assert_eq!(qe_identity.qe_identity.id, "TD_QE");
+        assert_eq!(qe_identity.qe_identity.isvprodid, 2);
+        assert_eq!(qe_identity.qe_identity.mrsigner.len(), 32);
+        assert_eq!(qe_identity.issuer_chain.len(), 2);
+    }
+
+    #[test]
+    fn get_pck_cert() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let pck_chain = td_quote.pck_chain().unwrap();
+        let issuer_chain = [
+            pck_chain.pck_ca.to_pem().unwrap(),
+            pck_chain.root_ca.to_pem().unwrap(),
+        ]
+        .concat();
+        let base_url = serve_requiring_header(
+            Some("Ocp-Apim-Subscription-Key: key"),
+            vec![MockResponse {
+                path: "/sgx/certification/v4/pckcert?encrypted_ppid=aa&cpusvn=01&pcesvn=0b00&pceid=0000".into(),
+                headers: vec![
+                    (
+                        "SGX-PCK-Certificate-Issuer-Chain",
+                        url_encode(&String::from_utf8(issuer_chain).unwrap().into()),
+                    ),
+                    ("SGX-TCBm", "010b00".into()),
+                    ("SGX-FMSPC", "B0C06F000000".into()),
+                ],
+                body: pck_chain.pck.to_pem().unwrap(),
+            }],
+        );
+        let client = PcsClient::new(base_url).with_api_key("key");
+
+        let pck_cert = client.get_pck_cert("aa", "01", "0b00", "0000").unwrap();
+        assert_eq!(
+            pck_cert.cert.to_der().unwrap(),
+            pck_chain.pck.to_der().unwrap()
+        );
+        assert_eq!(pck_cert.issuer_chain.len(), 2);
+        assert_eq!(
+            pck_cert.issuer_chain[0].to_der().unwrap(),
+            pck_chain.pck_ca.to_der().unwrap()
+        );
+        assert_eq!(pck_cert.tcbm, "010b00");
+        assert_eq!(pck_cert.fmspc, "B0C06F000000");
+    }
+
+    #[test]
+    fn get_crls() {
+        let collateral = collateral();
+        let pck_crl = hex::decode(collateral["pck_crl"].as_str().unwrap()).unwrap();
+        let root_ca_crl = hex::decode(collateral["root_ca_crl"].as_str().unwrap()).unwrap();
+        let base_url = serve(vec![
+            MockResponse {
+                path: "/sgx/certification/v4/pckcrl?ca=platform&encoding=der".into(),
+                headers: vec![(
+                    PCK_CRL_ISSUER_CHAIN_HEADER,
+                    url_encode(&collateral["pck_crl_issuer_chain"]),
+                )],
+                body: pck_crl,
+            },
+            MockResponse {
+                path: "/sgx/certification/v4/rootcacrl".into(),
+                headers: vec![],
+                body: root_ca_crl,
+            },
+        ]);
+        let client = PcsClient::new(base_url);
+
+        let pck_crl = client.get_pck_crl(PckCa::Platform).unwrap();
+        assert_eq!(pck_crl.issuer_chain.len(), 2);
+        let issuer = pck_crl.issuer_chain[0].public_key().unwrap();
+        assert!(pck_crl.crl.verify(&issuer).unwrap());
+        assert!(format!("{pck_crl:?}").contains("Intel SGX PCK Platform CA"));
+
+        let root_ca_crl = client.get_root_ca_crl().unwrap();
+        let root_ca = pck_crl.issuer_chain[1].public_key().unwrap();
+        assert!(root_ca_crl.verify(&root_ca).unwrap());
+    }
+
+    #[test]
+    fn reject_missing_issuer_chain() {
+        let base_url = serve(vec![MockResponse {
+            path: "/tdx/certification/v4/qe/identity".into(),
+            headers: vec![],
+            body: b"{}".to_vec(),
+        }]);
+        let client = PcsClient::new(base_url);
+        assert!(matches!(
+            client.get_qe_identity(),
+            Err(PcsError::MissingHeader(QE_IDENTITY_ISSUER_CHAIN_HEADER))
+        ));
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// Types are based on "Intel TDX DCAP: Quote Generation Library and Quote Verification Library",
+// Revision 0.9, Appendix 3 (Quote Format), and "Intel TDX DCAP: Quote Generation Library and
+// Quote Verification Library", Revision 0.14, Appendix 3 (Version 5 Quote Format)
+
+use crate::reader::{Reader, Truncated};
+use az_cvm_vtpm::tdx::{PolicyError, Rtmr, TdAttributes, TdPolicy, TdReport, Xfam};
+use std::mem::size_of;
+use thiserror::Error;
+use zerocopy::byteorder::little_endian::{U16, U32};
+use zerocopy::{AsBytes, FromBytes, FromZeroes, Unaligned};
+
+#[cfg(feature = "verifier")]
+mod verify;
+#[cfg(feature = "verifier")]
+pub(crate) use verify::{check_crl, ecdsa_sig, intel_root_ca, verify_chain};
+#[cfg(feature = "verifier")]
+pub use verify::{PckChain, VerifyError};
+
+const QUOTE_VERSION_4: u16 = 4;
+const QUOTE_VERSION_5: u16 = 5;
+const BODY_TYPE_TD_REPORT_1_0: u16 = 2;
+const BODY_TYPE_TD_REPORT_1_5: u16 = 3;
+const TEE_TYPE_TDX: u32 = 0x81;
+const ATTESTATION_KEY_TYPE_ECDSA_P256: u16 = 2;
+pub const CERT_DATA_TYPE_PCK_CERT_CHAIN: u16 = 5;
+pub const CERT_DATA_TYPE_QE_REPORT: u16 = 6;
+
+#[derive(Error, Debug)]
+pub enum QuoteError {
+    #[error("quote truncated, expected at least {0} more bytes, got {1}")]
+    Truncated(usize, usize),
+    #[error("unsupported quote version {0}")]
+    UnsupportedVersion(u16),
+    #[error("unsupported TEE type {0:#x}")]
+    UnsupportedTeeType(u32),
+    #[error("unsupported attestation key type {0}")]
+    UnsupportedAttestationKeyType(u16),
+    #[error("unsupported certification data type {0}")]
+    UnsupportedCertificationDataType(u16),
+    #[error("unsupported quote body type {0}")]
+    UnsupportedBodyType(u16),
+    #[error("invalid quote body size (expected {0} bytes, found {1})")]
+    BodySizeMismatch(usize, usize),
+}
+
+#[repr(C)]
+#[derive(AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, PartialEq)]
+pub struct QuoteHeader {
+    version: U16,
+    attestation_key_type: U16,
+    tee_type: U32,
+    _reserved: [u8; 4],
+    pub qe_vendor_id: [u8; 16],
+    pub user_data: [u8; 20],
+}
+
+impl QuoteHeader {
+    pub fn version(&self) -> u16 {
+        self.version.get()
+    }
+
+    pub fn attestation_key_type(&self) -> u16 {
+        self.attestation_key_type.get()
+    }
+
+    pub fn tee_type(&self) -> u32 {
+        self.tee_type.get()
+    }
+
+    fn validate(&self) -> Result<(), QuoteError> {
+        if !matches!(self.version(), QUOTE_VERSION_4 | QUOTE_VERSION_5) {
+            return Err(QuoteError::UnsupportedVersion(self.version()));
+        }
+        if self.tee_type() != TEE_TYPE_TDX {
+            return Err(QuoteError::UnsupportedTeeType(self.tee_type()));
+        }
+        if self.attestation_key_type() != ATTESTATION_KEY_TYPE_ECDSA_P256 {
+            return Err(QuoteError::UnsupportedAttestationKeyType(
+                self.attestation_key_type(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The TD quote body, with the TD measurements and report data taken from the `TdReport`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, PartialEq)]
+pub struct TdQuoteBody {
+    pub tee_tcb_svn: [u8; 16],
+    pub mrseam: [u8; 48],
+    pub mrsignerseam: [u8; 48],
+    pub seamattributes: [u8; 8],
+    pub tdattributes: [u8; 8],
+    pub xfam: [u8; 8],
+    pub mrtd: [u8; 48],
+    pub mrconfigid: [u8; 48],
+    pub mrowner: [u8; 48],
+    pub mrownerconfig: [u8; 48],
+    pub rtmr: [Rtmr; 4],
+    pub reportdata: [u8; 64],
+}
+
+/// The fields a TD 1.5 quote body appends to `TdQuoteBody`
+#[repr(C)]
+#[derive(AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, PartialEq)]
+pub struct TdQuoteBodyExt {
+    pub tee_tcb_svn2: [u8; 16],
+    pub mrservicetd: [u8; 48],
+}
+
+/// The SGX report of the Quoting Enclave (QE)
+#[repr(C)]
+#[derive(AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, PartialEq)]
+pub struct QeReport {
+    pub cpusvn: [u8; 16],
+    miscselect: U32,
+    _reserved_1: [u8; 28],
+    pub attributes: [u8; 16],
+    pub mrenclave: [u8; 32],
+    _reserved_2: [u8; 32],
+    pub mrsigner: [u8; 32],
+    _reserved_3: [u8; 96],
+    isv_prod_id: U16,
+    isv_svn: U16,
+    _reserved_4: [u8; 60],
+    pub reportdata: [u8; 64],
+}
+
+impl QeReport {
+    pub fn miscselect(&self) -> u32 {
+        self.miscselect.get()
+    }
+
+    pub fn isv_prod_id(&self) -> u16 {
+        self.isv_prod_id.get()
+    }
+
+    pub fn isv_svn(&self) -> u16 {
+        self.isv_svn.get()
+    }
+}
+
+const _: () = assert!(size_of::<QuoteHeader>() == 48);
+const _: () = assert!(size_of::<TdQuoteBody>() == 584);
+const _: () = assert!(size_of::<TdQuoteBodyExt>() == 64);
+const _: () = assert!(size_of::<QeReport>() == 384);
+
+/// Certification data of a given type, e.g. the PEM-encoded PCK certificate chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct CertificationData {
+    pub cert_type: u16,
+    pub data: Vec<u8>,
+}
+
+/// Certification data of type 6, which certifies the attestation key by a QE report
+#[derive(Clone, Debug, PartialEq)]
+pub struct QeReportCertificationData {
+    pub qe_report: QeReport,
+    pub qe_report_signature: [u8; 64],
+    pub qe_auth_data: Vec<u8>,
+    pub certification_data: CertificationData,
+}
+
+/// ECDSA-P256 signature data of a quote
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuoteSignatureData {
+    pub signature: [u8; 64],
+    pub attestation_key: [u8; 64],
+    pub qe_report_certification_data: QeReportCertificationData,
+}
+
+/// A TDX quote, as returned by `imds::get_td_quote()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TdQuote {
+    pub header: QuoteHeader,
+    pub body: TdQuoteBody,
+    /// Only present in v5 quotes with a TD 1.5 body
+    pub body_ext: Option<TdQuoteBodyExt>,
+    pub signature_data: QuoteSignatureData,
+}
+
+impl From<Truncated> for QuoteError {
+    fn from(Truncated(expected, remaining): Truncated) -> Self {
+        Self::Truncated(expected, remaining)
+    }
+}
+
+impl CertificationData {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteError> {
+        let cert_type = reader.u16()?;
+        let size = reader.u32()? as usize;
+        let data = reader.take(size)?.to_vec();
+        Ok(Self { cert_type, data })
+    }
+}
+
+impl QeReportCertificationData {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteError> {
+        let qe_report = reader.read()?;
+        let qe_report_signature = reader.read()?;
+        let qe_auth_data_size = reader.u16()? as usize;
+        let qe_auth_data = reader.take(qe_auth_data_size)?.to_vec();
+        let certification_data = CertificationData::read(reader)?;
+        Ok(Self {
+            qe_report,
+            qe_report_signature,
+            qe_auth_data,
+            certification_data,
+        })
+    }
+}
+
+impl QuoteSignatureData {
+    fn read(reader: &mut Reader) -> Result<Self, QuoteError> {
+        let signature = reader.read()?;
+        let attestation_key = reader.read()?;
+        let CertificationData { cert_type, data } = CertificationData::read(reader)?;
+        if cert_type != CERT_DATA_TYPE_QE_REPORT {
+            return Err(QuoteError::UnsupportedCertificationDataType(cert_type));
+        }
+        let qe_report_certification_data =
+            QeReportCertificationData::read(&mut Reader::new(&data))?;
+        Ok(Self {
+            signature,
+            attestation_key,
+            qe_report_certification_data,
+        })
+    }
+}
+
+impl TdQuoteBody {
+    /// Decode the TD attributes, unknown bits are retained
+    pub fn td_attributes(&self) -> TdAttributes {
+        TdAttributes::from_bits_retain(u64::from_le_bytes(self.tdattributes))
+    }
+
+    /// Decode the XFAM, unknown bits are retained
+    pub fn xfam_features(&self) -> Xfam {
+        Xfam::from_bits_retain(u64::from_le_bytes(self.xfam))
+    }
+}
+
+impl TdQuote {
+    /// Check the TD attributes and XFAM of the quote body against a policy
+    pub fn check_policy(&self, policy: &TdPolicy) -> Result<(), PolicyError> {
+        policy.check(self.body.td_attributes(), self.body.xfam_features())
+    }
+
+    /// The type of the quote body, v4 quotes always have a TD 1.0 body
+    pub fn body_type(&self) -> u16 {
+        match self.body_ext {
+            Some(_) => BODY_TYPE_TD_REPORT_1_5,
+            None => BODY_TYPE_TD_REPORT_1_0,
+        }
+    }
+
+    /// The bytes that are signed by the attestation key, i.e. the header, the body descriptor
+    /// of v5 quotes and the body
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header.as_bytes().to_vec();
+        if self.header.version() == QUOTE_VERSION_5 {
+            let body_size =
+                size_of::<TdQuoteBody>() + self.body_ext.map_or(0, |_| size_of::<TdQuoteBodyExt>());
+            bytes.extend_from_slice(&self.body_type().to_le_bytes());
+            bytes.extend_from_slice(&(body_size as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(self.body.as_bytes());
+        if let Some(body_ext) = &self.body_ext {
+            bytes.extend_from_slice(body_ext.as_bytes());
+        }
+        bytes
+    }
+
+    /// Check whether the quote body has been produced from the given `TdReport`
+    pub fn matches_td_report(&self, td_report: &TdReport) -> bool {
+        let body = &self.body;
+        let tee_tcb_info = &td_report.tee_tcb_info;
+        let tdinfo = &td_report.tdinfo;
+        let body_ext_matches = self.body_ext.is_none_or(|body_ext| {
+            body_ext.tee_tcb_svn2 == tee_tcb_info.tee_tcb_svn2
+                && body_ext.mrservicetd == tdinfo.servtd_hash
+        });
+        body_ext_matches
+            && body.tee_tcb_svn == tee_tcb_info.tee_tcb_svn
+            && body.mrseam == tee_tcb_info.mrseam
+            && body.mrsignerseam == tee_tcb_info.mrsignerseam
+            && body.seamattributes == tee_tcb_info.attributes
+            && body.tdattributes == tdinfo.attributes
+            && body.xfam == tdinfo.xfam
+            && body.mrtd == tdinfo.mrtd
+            && body.mrconfigid == tdinfo.mrconfigid
+            && body.mrowner == tdinfo.mrowner
+            && body.mrownerconfig == tdinfo.mrownerconfig
+            && body.rtmr == tdinfo.rtrm
+            && body.reportdata == td_report.report_mac.reportdata
+    }
+}
+
+/// Read the body descriptor of a v5 quote and return whether the body is a TD 1.5 body
+fn read_body_descriptor(reader: &mut Reader) -> Result<bool, QuoteError> {
+    let body_type = reader.u16()?;
+    let body_size = reader.u32()? as usize;
+    let (is_td_1_5, expected_size) = match body_type {
+        BODY_TYPE_TD_REPORT_1_0 => (false, size_of::<TdQuoteBody>()),
+        BODY_TYPE_TD_REPORT_1_5 => (true, size_of::<TdQuoteBody>() + size_of::<TdQuoteBodyExt>()),
+        _ => return Err(QuoteError::UnsupportedBodyType(body_type)),
+    };
+    if body_size != expected_size {
+        return Err(QuoteError::BodySizeMismatch(expected_size, body_size));
+    }
+    Ok(is_td_1_5)
+}
+
+/// Parse raw bytes into a TdQuote, v4 and v5 quotes are supported. Trailing bytes after the
+/// signature data are ignored.
+pub fn parse(bytes: &[u8]) -> Result<TdQuote, QuoteError> {
+    let mut reader = Reader::new(bytes);
+    let header: QuoteHeader = reader.read()?;
+    header.validate()?;
+    let is_td_1_5 = header.version() == QUOTE_VERSION_5 && read_body_descriptor(&mut reader)?;
+    let body = reader.read()?;
+    let body_ext = match is_td_1_5 {
+        true => Some(reader.read()?),
+        false => None,
+    };
+    let signature_data_size = reader.u32()? as usize;
+    let signature_data_bytes = reader.take(signature_data_size)?;
+    let signature_data = QuoteSignatureData::read(&mut Reader::new(signature_data_bytes))?;
+    Ok(TdQuote {
+        header,
+        body,
+        body_ext,
+        signature_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use az_cvm_vtpm::hcl::HclReport;
+
+    #[test]
+    fn parse_td_quote() {
+        let bytes = include_bytes!("../../test/td-quote.bin");
+        let td_quote = parse(bytes).unwrap();
+        assert_eq!(td_quote.header.version(), 4);
+        assert_eq!(td_quote.header.tee_type(), TEE_TYPE_TDX);
+        assert_eq!(
+            td_quote.header.qe_vendor_id,
+            [
+                0x93, 0x9a, 0x72, 0x33, 0xf7, 0x9c, 0x4c, 0xa9, 0x94, 0x0a, 0x0d, 0xb3, 0x95, 0x7f,
+                0x06, 0x07
+            ]
+        );
+
+        let qe_data = &td_quote.signature_data.qe_report_certification_data;
+        assert_eq!(qe_data.qe_auth_data.len(), 32);
+        let cert_data = &qe_data.certification_data;
+        assert_eq!(cert_data.cert_type, CERT_DATA_TYPE_PCK_CERT_CHAIN);
+        assert!(cert_data.data.starts_with(b"-----BEGIN CERTIFICATE-----"));
+
+        assert_eq!(td_quote.body.td_attributes(), TdAttributes::SEPT_VE_DISABLE);
+        td_quote.check_policy(&TdPolicy::default()).unwrap();
+
+        let signed_bytes = td_quote.signed_bytes();
+        assert_eq!(signed_bytes, bytes[..632]);
+        assert_eq!(td_quote.body_type(), BODY_TYPE_TD_REPORT_1_0);
+        assert_eq!(td_quote.body_ext, None);
+    }
+
+    #[test]
+    fn parse_td_quote_v5() {
+        let bytes = include_bytes!("../../test/td-quote-v5.bin");
+        let td_quote = parse(bytes).unwrap();
+        assert_eq!(td_quote.header.version(), 5);
+        assert_eq!(td_quote.body_type(), BODY_TYPE_TD_REPORT_1_5);
+        let body_ext = td_quote.body_ext.unwrap();
+        assert_eq!(body_ext.tee_tcb_svn2[..3], [0x0d, 0x01, 0x03]);
+        assert_eq!(body_ext.mrservicetd, [0; 48]);
+        assert_eq!(td_quote.signed_bytes(), bytes[..702]);
+
+        let qe_data = &td_quote.signature_data.qe_report_certification_data;
+        assert_eq!(
+            qe_data.certification_data.cert_type,
+            CERT_DATA_TYPE_PCK_CERT_CHAIN
+        );
+
+        let mut quote = bytes.to_vec();
+        quote[48] = 1;
+        assert!(matches!(
+            parse(&quote),
+            Err(QuoteError::UnsupportedBodyType(1))
+        ));
+
+        let mut quote = bytes.to_vec();
+        quote[50] = 0;
+        assert!(matches!(
+            parse(&quote),
+            Err(QuoteError::BodySizeMismatch(648, 0x200))
+        ));
+    }
+
+    #[test]
+    fn reject_malformed_quotes() {
+        let bytes = include_bytes!("../../test/td-quote.bin");
+
+        let mut quote = bytes.to_vec();
+        quote[0] = 3;
+        assert!(matches!(
+            parse(&quote),
+            Err(QuoteError::UnsupportedVersion(3))
+        ));
+
+        let mut quote = bytes.to_vec();
+        quote[4] = 0;
+        assert!(matches!(
+            parse(&quote),
+            Err(QuoteError::UnsupportedTeeType(0))
+        ));
+
+        assert!(matches!(
+            parse(&bytes[..1000]),
+            Err(QuoteError::Truncated(_, _))
+        ));
+    }
+
+    #[test]
+    fn match_td_report() {
+        let td_quote = parse(include_bytes!("../../test/td-quote.bin")).unwrap();
+        let bytes = include_bytes!("../../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let mut td_report: TdReport = hcl_report.try_into().unwrap();
+        assert!(!td_quote.matches_td_report(&td_report));
+
+        let body = &td_quote.body;
+        td_report.tee_tcb_info.tee_tcb_svn = body.tee_tcb_svn;
+        td_report.tee_tcb_info.mrseam = body.mrseam;
+        td_report.tee_tcb_info.mrsignerseam = body.mrsignerseam;
+        td_report.tee_tcb_info.attributes = body.seamattributes;
+        td_report.tdinfo.attributes = body.tdattributes;
+        td_report.tdinfo.xfam = body.xfam;
+        td_report.tdinfo.mrtd = body.mrtd;
+        td_report.tdinfo.mrconfigid = body.mrconfigid;
+        td_report.tdinfo.mrowner = body.mrowner;
+        td_report.tdinfo.mrownerconfig = body.mrownerconfig;
+        td_report.tdinfo.rtrm = body.rtmr;
+        td_report.report_mac.reportdata = body.reportdata;
+        assert!(td_quote.matches_td_report(&td_report));
+
+        let mut td_quote = td_quote;
+        td_quote.body_ext = Some(TdQuoteBodyExt {
+            tee_tcb_svn2: td_report.tee_tcb_info.tee_tcb_svn2,
+            mrservicetd: [0xab; 48],
+        });
+        assert!(!td_quote.matches_td_report(&td_report));
+        td_report.tdinfo.servtd_hash = [0xab; 48];
+        assert!(td_quote.matches_td_report(&td_report));
+    }
+}
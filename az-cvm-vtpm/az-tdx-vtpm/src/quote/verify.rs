@@ -0,0 +1,456 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{TdQuote, CERT_DATA_TYPE_PCK_CERT_CHAIN};
+use crate::pcs::Crl;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::Public;
+use openssl::sha::sha256;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{CrlStatus, X509CrlRef, X509Ref, X509StoreContext, X509VerifyResult, X509};
+use thiserror::Error;
+use zerocopy::AsBytes;
+
+/// Intel SGX Root CA, the trust anchor of TDX quotes
+const INTEL_SGX_ROOT_CA: &[u8] = include_bytes!("intel-sgx-root-ca.pem");
+
+#[non_exhaustive]
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    #[error("openssl error")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+    #[error("unsupported certification data type {0}")]
+    UnsupportedCertificationDataType(u16),
+    #[error("wrong amount of certificates (expected {0:?}, found {1:?})")]
+    WrongAmount(usize, usize),
+    #[error("root CA is not the Intel SGX Root CA")]
+    RootCaMismatch,
+    #[error("PCK CA is not signed by the Intel SGX Root CA")]
+    PckCaNotSignedByRootCa,
+    #[error("PCK certificate is not signed by PCK CA")]
+    PckNotSignedByPckCa,
+    #[error("invalid PCK chain: {0}")]
+    InvalidPckChain(X509VerifyResult),
+    #[error("CRL is not signed by its issuer")]
+    CrlSignature,
+    #[error("certificate has been revoked")]
+    Revoked,
+    #[error("QE report is not signed by PCK")]
+    QeReportSignature,
+    #[error("QE report does not bind the attestation key")]
+    AttestationKeyBinding,
+    #[error("quote is not signed by the attestation key")]
+    QuoteSignature,
+}
+
+/// PCK certificate chain, as found in the certification data of a TDX quote
+pub struct PckChain {
+    pub pck: X509,
+    pub pck_ca: X509,
+    pub root_ca: X509,
+}
+
+impl PckChain {
+    /// Validate the chain up to the pinned Intel SGX Root CA. Besides the signatures, this
+    /// checks the validity periods of the certificates and the CA constraints of the issuers.
+    /// Revocation is checked separately with `check_revocation()`.
+    pub fn validate(&self) -> Result<(), VerifyError> {
+        let intel_root_ca = intel_root_ca()?;
+        if self.root_ca.to_der()? != intel_root_ca.to_der()? {
+            return Err(VerifyError::RootCaMismatch);
+        }
+        self.validate_with_root_ca(intel_root_ca)
+    }
+
+    fn validate_with_root_ca(&self, root_ca: X509) -> Result<(), VerifyError> {
+        let root_ca_pubkey = root_ca.public_key()?;
+        if !self.pck_ca.verify(&root_ca_pubkey)? {
+            return Err(VerifyError::PckCaNotSignedByRootCa);
+        }
+
+        let pck_ca_pubkey = self.pck_ca.public_key()?;
+        if !self.pck.verify(&pck_ca_pubkey)? {
+            return Err(VerifyError::PckNotSignedByPckCa);
+        }
+
+        let result = verify_chain(&self.pck, std::slice::from_ref(&self.pck_ca), root_ca)?;
+        if result != X509VerifyResult::OK {
+            return Err(VerifyError::InvalidPckChain(result));
+        }
+
+        Ok(())
+    }
+
+    /// Check that neither the PCK certificate nor the PCK CA has been revoked, e.g. with the
+    /// CRLs of a `pcs::Collateral`. The freshness of the CRLs is not checked, this is up to the
+    /// caller which retrieved them.
+    pub fn check_revocation(
+        &self,
+        pck_crl: &Crl,
+        root_ca_crl: &X509CrlRef,
+    ) -> Result<(), VerifyError> {
+        check_crl(&pck_crl.crl, &self.pck_ca, &self.pck)?;
+        check_crl(root_ca_crl, &self.root_ca, &self.pck_ca)?;
+        Ok(())
+    }
+}
+
+/// Verify a certificate chain up to the given root CA with openssl, which checks the signatures,
+/// the validity periods of the certificates and the CA constraints of the issuers
+pub(crate) fn verify_chain(
+    cert: &X509Ref,
+    intermediates: &[X509],
+    root_ca: X509,
+) -> Result<X509VerifyResult, openssl::error::ErrorStack> {
+    let mut store = X509StoreBuilder::new()?;
+    store.add_cert(root_ca)?;
+    let store = store.build();
+    let mut untrusted = Stack::new()?;
+    for intermediate in intermediates {
+        untrusted.push(intermediate.clone())?;
+    }
+    let mut context = X509StoreContext::new()?;
+    context.init(&store, cert, &untrusted, |context| {
+        context.verify_cert()?;
+        Ok(context.error())
+    })
+}
+
+/// Check that a CRL is signed by the issuer of a certificate and does not list the certificate
+pub(crate) fn check_crl(
+    crl: &X509CrlRef,
+    issuer: &X509Ref,
+    cert: &X509,
+) -> Result<(), VerifyError> {
+    if !crl.verify(&*issuer.public_key()?)? {
+        return Err(VerifyError::CrlSignature);
+    }
+    match crl.get_by_cert(cert) {
+        CrlStatus::NotRevoked => Ok(()),
+        CrlStatus::Revoked(_) | CrlStatus::RemoveFromCrl(_) => Err(VerifyError::Revoked),
+    }
+}
+
+/// The pinned Intel SGX Root CA
+pub(crate) fn intel_root_ca() -> Result<X509, openssl::error::ErrorStack> {
+    X509::from_pem(INTEL_SGX_ROOT_CA)
+}
+
+/// Convert a raw r || s signature into an EcdsaSig
+pub(crate) fn ecdsa_sig(bytes: &[u8; 64]) -> Result<EcdsaSig, openssl::error::ErrorStack> {
+    let r = BigNum::from_slice(&bytes[..32])?;
+    let s = BigNum::from_slice(&bytes[32..])?;
+    let sig = EcdsaSig::from_private_components(r, s)?;
+    Ok(sig)
+}
+
+/// Convert a raw x || y P-256 public key into an EcKey
+fn ec_key(bytes: &[u8; 64]) -> Result<EcKey<Public>, VerifyError> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let mut ctx = BigNumContext::new()?;
+    let point = EcPoint::from_bytes(&group, &[&[0x04], &bytes[..]].concat(), &mut ctx)?;
+    let key = EcKey::from_public_key(&group, &point)?;
+    Ok(key)
+}
+
+impl TdQuote {
+    /// Build the PCK certificate chain from the quote's certification data
+    pub fn pck_chain(&self) -> Result<PckChain, VerifyError> {
+        let cert_data = &self
+            .signature_data
+            .qe_report_certification_data
+            .certification_data;
+        if cert_data.cert_type != CERT_DATA_TYPE_PCK_CERT_CHAIN {
+            return Err(VerifyError::UnsupportedCertificationDataType(
+                cert_data.cert_type,
+            ));
+        }
+
+        let certs = X509::stack_from_pem(&cert_data.data)?;
+        if certs.len() != 3 {
+            return Err(VerifyError::WrongAmount(3, certs.len()));
+        }
+
+        let chain = PckChain {
+            pck: certs[0].clone(),
+            pck_ca: certs[1].clone(),
+            root_ca: certs[2].clone(),
+        };
+        Ok(chain)
+    }
+
+    /// Verify the quote against Intel's root of trust. This validates the PCK chain, the QE
+    /// report and its binding of the attestation key, and the quote's signature.
+    /// **Note:** this does not check whether the PCK certificate or the PCK CA has been
+    /// revoked, use `verify_with_crls()` for that.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        let pck_chain = self.pck_chain()?;
+        pck_chain.validate()?;
+        self.verify_with_pck_chain(&pck_chain)
+    }
+
+    /// Verify the quote against Intel's root of trust, like `verify()`, and check that neither
+    /// the PCK certificate nor the PCK CA has been revoked
+    ///
+    /// # Arguments
+    ///
+    /// * `pck_crl` - The CRL of the CA that issued the PCK certificate
+    /// * `root_ca_crl` - The CRL of the Intel SGX Root CA
+    pub fn verify_with_crls(
+        &self,
+        pck_crl: &Crl,
+        root_ca_crl: &X509CrlRef,
+    ) -> Result<(), VerifyError> {
+        let pck_chain = self.pck_chain()?;
+        pck_chain.validate()?;
+        pck_chain.check_revocation(pck_crl, root_ca_crl)?;
+        self.verify_with_pck_chain(&pck_chain)
+    }
+
+    fn verify_with_pck_chain(&self, pck_chain: &PckChain) -> Result<(), VerifyError> {
+        self.verify_qe_report(&pck_chain.pck)?;
+        self.verify_attestation_key_binding()?;
+        self.verify_signature()?;
+        Ok(())
+    }
+
+    /// Verify the QE report's signature
+    ///
+    /// # Arguments
+    ///
+    /// * `pck` - The PCK certificate of the platform
+    pub fn verify_qe_report(&self, pck: &X509) -> Result<(), VerifyError> {
+        let qe_data = &self.signature_data.qe_report_certification_data;
+        let sig = ecdsa_sig(&qe_data.qe_report_signature)?;
+        let pck_pubkey = pck.public_key()?.ec_key()?;
+        let digest = sha256(qe_data.qe_report.as_bytes());
+        if !sig.verify(&digest, &pck_pubkey)? {
+            return Err(VerifyError::QeReportSignature);
+        }
+        Ok(())
+    }
+
+    /// Verify that the QE report's report data contains the hash of the attestation key and
+    /// the QE authentication data
+    pub fn verify_attestation_key_binding(&self) -> Result<(), VerifyError> {
+        let signature_data = &self.signature_data;
+        let qe_data = &signature_data.qe_report_certification_data;
+        let hash = sha256(&[&signature_data.attestation_key[..], &qe_data.qe_auth_data].concat());
+        let (report_data_hash, padding) = qe_data.qe_report.reportdata.split_at(hash.len());
+        if report_data_hash != hash || padding.iter().any(|&b| b != 0) {
+            return Err(VerifyError::AttestationKeyBinding);
+        }
+        Ok(())
+    }
+
+    /// Verify the quote's signature over the header and body with the attestation key
+    pub fn verify_signature(&self) -> Result<(), VerifyError> {
+        let signature_data = &self.signature_data;
+        let sig = ecdsa_sig(&signature_data.signature)?;
+        let attestation_key = ec_key(&signature_data.attestation_key)?;
+        let digest = sha256(&self.signed_bytes());
+        if !sig.verify(&digest, &attestation_key)? {
+            return Err(VerifyError::QuoteSignature);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse;
+    use super::*;
+    use crate::pcs::tests::collateral;
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::extension::{BasicConstraints, KeyUsage};
+    use openssl::x509::{X509Builder, X509Crl, X509NameBuilder};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn verify_td_quote() {
+        let bytes = include_bytes!("../../test/td-quote.bin");
+        let td_quote = parse(bytes).unwrap();
+        td_quote.verify().unwrap();
+    }
+
+    #[test]
+    fn verify_td_quote_v5() {
+        let bytes = include_bytes!("../../test/td-quote-v5.bin");
+        let mut td_quote = parse(bytes).unwrap();
+        td_quote.verify().unwrap();
+
+        let body_ext = td_quote.body_ext.as_mut().unwrap();
+        body_ext.mrservicetd[0] ^= 1;
+        assert!(matches!(
+            td_quote.verify(),
+            Err(VerifyError::QuoteSignature)
+        ));
+    }
+
+    #[test]
+    fn reject_tampered_quotes() {
+        let bytes = include_bytes!("../../test/td-quote.bin");
+        let td_quote = parse(bytes).unwrap();
+
+        let mut quote = td_quote.clone();
+        quote.body.reportdata[0] ^= 1;
+        assert!(matches!(quote.verify(), Err(VerifyError::QuoteSignature)));
+
+        let mut quote = td_quote.clone();
+        quote.signature_data.attestation_key[0] ^= 1;
+        assert!(matches!(
+            quote.verify_attestation_key_binding(),
+            Err(VerifyError::AttestationKeyBinding)
+        ));
+
+        let mut quote = td_quote.clone();
+        let qe_data = &mut quote.signature_data.qe_report_certification_data;
+        qe_data.qe_report.mrsigner[0] ^= 1;
+        assert!(matches!(
+            quote.verify(),
+            Err(VerifyError::QeReportSignature)
+        ));
+    }
+
+    #[test]
+    fn reject_foreign_root_ca() {
+        let bytes = include_bytes!("../../test/td-quote.bin");
+        let td_quote = parse(bytes).unwrap();
+        let mut pck_chain = td_quote.pck_chain().unwrap();
+        pck_chain.validate().unwrap();
+
+        pck_chain.root_ca = pck_chain.pck_ca.clone();
+        assert!(matches!(
+            pck_chain.validate(),
+            Err(VerifyError::RootCaMismatch)
+        ));
+    }
+
+    /// Build a certificate, which is valid from `validity.0` to `validity.1` days from now
+    fn build_cert(
+        name: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+        is_ca: bool,
+        validity: (i64, i64),
+    ) -> X509 {
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_nid(Nid::COMMONNAME, name).unwrap();
+        let subject = subject.build();
+        let (issuer_name, issuer_key) = match issuer {
+            Some((issuer_cert, issuer_key)) => (issuer_cert.subject_name(), issuer_key),
+            None => (&*subject, key),
+        };
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_issuer_name(issuer_name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let not_before = Asn1Time::from_unix(now + validity.0 * 86400).unwrap();
+        let not_after = Asn1Time::from_unix(now + validity.1 * 86400).unwrap();
+        builder.set_not_before(&not_before).unwrap();
+        builder.set_not_after(&not_after).unwrap();
+        let mut basic_constraints = BasicConstraints::new();
+        basic_constraints.critical();
+        if is_ca {
+            basic_constraints.ca();
+            let key_usage = KeyUsage::new().critical().key_cert_sign().build().unwrap();
+            builder.append_extension(key_usage).unwrap();
+        }
+        let basic_constraints = basic_constraints.build().unwrap();
+        builder.append_extension(basic_constraints).unwrap();
+        builder.sign(issuer_key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    fn ec_private_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reject_invalid_pck_chains() {
+        let root_key = ec_private_key();
+        let ca_key = ec_private_key();
+        let pck_key = ec_private_key();
+        let root_ca = build_cert("root", &root_key, None, true, (-1, 10));
+        let root = Some((&root_ca, &root_key));
+
+        let pck_ca = build_cert("ca", &ca_key, root, true, (-1, 10));
+        let pck_issuer = Some((&pck_ca, &ca_key));
+        let pck = build_cert("pck", &pck_key, pck_issuer, false, (-1, 10));
+        let expired_pck = build_cert("pck", &pck_key, pck_issuer, false, (-10, -1));
+        let pck_chain = PckChain {
+            pck,
+            pck_ca: pck_ca.clone(),
+            root_ca: root_ca.clone(),
+        };
+        pck_chain.validate_with_root_ca(root_ca.clone()).unwrap();
+
+        let pck_chain = PckChain {
+            pck: expired_pck,
+            ..pck_chain
+        };
+        assert!(matches!(
+            pck_chain.validate_with_root_ca(root_ca.clone()),
+            Err(VerifyError::InvalidPckChain(_))
+        ));
+
+        // a leaf certificate must not issue PCK certificates
+        let leaf = build_cert("leaf", &ca_key, root, false, (-1, 10));
+        let pck = build_cert("pck", &pck_key, Some((&leaf, &ca_key)), false, (-1, 10));
+        let pck_chain = PckChain {
+            pck,
+            pck_ca: leaf,
+            root_ca: root_ca.clone(),
+        };
+        assert!(matches!(
+            pck_chain.validate_with_root_ca(root_ca),
+            Err(VerifyError::InvalidPckChain(_))
+        ));
+    }
+
+    #[test]
+    fn check_pck_revocation() {
+        let bytes = include_bytes!("../../test/td-quote.bin");
+        let td_quote = parse(bytes).unwrap();
+        let pck_chain = td_quote.pck_chain().unwrap();
+
+        let collateral = collateral();
+        let crl = |name: &str| {
+            let der = hex::decode(collateral[name].as_str().unwrap()).unwrap();
+            X509Crl::from_der(&der).unwrap()
+        };
+        let pck_crl = Crl {
+            crl: crl("pck_crl"),
+            issuer_chain: vec![],
+        };
+        pck_chain
+            .check_revocation(&pck_crl, &crl("root_ca_crl"))
+            .unwrap();
+
+        assert!(matches!(
+            pck_chain.check_revocation(&pck_crl, &pck_crl.crl),
+            Err(VerifyError::CrlSignature)
+        ));
+
+        td_quote
+            .verify_with_crls(&pck_crl, &crl("root_ca_crl"))
+            .unwrap();
+        assert!(matches!(
+            td_quote.verify_with_crls(&pck_crl, &pck_crl.crl),
+            Err(VerifyError::CrlSignature)
+        ));
+    }
+}
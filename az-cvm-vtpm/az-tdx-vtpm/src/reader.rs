@@ -0,0 +1,50 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::mem::size_of;
+use zerocopy::byteorder::little_endian::{U16, U32, U64};
+use zerocopy::FromBytes;
+
+/// The input ended before the expected number of bytes (expected, remaining)
+pub(crate) struct Truncated(pub usize, pub usize);
+
+/// Little-endian cursor over a byte slice
+pub(crate) struct Reader<'a> {
+    pub bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], Truncated> {
+        if self.bytes.len() < len {
+            return Err(Truncated(len, self.bytes.len()));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub fn read<T: FromBytes>(&mut self) -> Result<T, Truncated> {
+        let bytes = self.take(size_of::<T>())?;
+        Ok(T::read_from(bytes).expect("length has been checked"))
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Truncated> {
+        self.read()
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Truncated> {
+        Ok(self.read::<U16>()?.get())
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Truncated> {
+        Ok(self.read::<U32>()?.get())
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Truncated> {
+        Ok(self.read::<U64>()?.get())
+    }
+}
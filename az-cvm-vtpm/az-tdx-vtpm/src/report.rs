@@ -1,22 +1,22 @@
 use crate::hcl::{self, HclReport};
-use crate::tdx::TdReport;
+use crate::tdx::{TdReport, TdReportError};
 use crate::vtpm;
-use bincode::deserialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ReportError {
-    #[error("deserialization error")]
-    Parse(#[from] Box<bincode::ErrorKind>),
+    #[error("TD report error")]
+    Parse(#[from] TdReportError),
     #[error("vTPM error")]
     Vtpm(#[from] vtpm::ReportError),
     #[error("HCL error")]
     Hcl(#[from] hcl::HclError),
 }
 
-/// Parse raw bytes into TdReport
+/// Parse raw bytes into TdReport, the input must be exactly 1024 bytes
 pub fn parse(bytes: &[u8]) -> Result<TdReport, ReportError> {
-    deserialize::<TdReport>(bytes).map_err(|e| e.into())
+    let td_report = TdReport::from_bytes(bytes)?;
+    Ok(td_report)
 }
 
 /// Fetch TdReport from vTPM and parse it
@@ -0,0 +1,809 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+// Evaluation follows "Intel TDX DCAP: Quote Generation Library and Quote Verification Library",
+// Revision 0.9, Section 4.1.2.4 (TCB Level Matching), the PCK certificate extensions are
+// described in "Intel SGX PCK Certificate and Certificate Revocation List Profile
+// Specification", Section 3.5
+
+use crate::pcs::{IsvTcbLevel, QeIdentity, SignedQeIdentity, SignedTcbInfo, TcbInfo, TcbStatus};
+use crate::quote::{check_crl, ecdsa_sig, intel_root_ca, verify_chain, TdQuote, VerifyError};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::sha::sha256;
+use openssl::x509::{X509CrlRef, X509VerifyResult, X509};
+use thiserror::Error;
+
+/// OID 1.2.840.113741.1.13.1, the SGX extension of PCK certificates
+const SGX_EXTENSION_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x01];
+const SGX_EXTENSION_TCB: u8 = 2;
+const SGX_EXTENSION_PCEID: u8 = 3;
+const SGX_EXTENSION_FMSPC: u8 = 4;
+const TCB_PCESVN: u8 = 17;
+const TCB_CPUSVN: u8 = 18;
+const DER_INTEGER: u8 = 0x02;
+const DER_OCTET_STRING: u8 = 0x04;
+const DER_OID: u8 = 0x06;
+const DER_SEQUENCE: u8 = 0x30;
+const DER_EXTENSIONS: u8 = 0xa3;
+const TDX_TCB_INFO_ID: &str = "TDX";
+const TDX_TCB_INFO_MIN_VERSION: u32 = 3;
+const TDX_QE_IDENTITY_ID: &str = "TD_QE";
+/// Components of `tee_tcb_svn` that describe the TDX module, which are matched against the TDX
+/// module identities of TCB Info instead of its TCB levels, if the module version is non-zero
+const TDX_MODULE_TCB_COMPONENTS: usize = 2;
+
+#[derive(Error, Debug)]
+pub enum TcbError {
+    #[error("openssl error")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+    #[error("quote verification error")]
+    Verify(#[from] VerifyError),
+    #[error("invalid DER encoding in PCK certificate")]
+    InvalidDer,
+    #[error("PCK certificate has no SGX extension")]
+    MissingSgxExtension,
+    #[error("issuer chain is not rooted in the Intel SGX Root CA")]
+    IssuerChain,
+    #[error("invalid issuer chain: {0}")]
+    InvalidIssuerChain(X509VerifyResult),
+    #[error("invalid collateral signature")]
+    Signature,
+    #[error("invalid collateral date {0}")]
+    InvalidDate(String),
+    #[error("collateral has been issued in the future")]
+    CollateralNotYetIssued,
+    #[error("collateral has expired")]
+    CollateralExpired,
+    #[error("TCB Info is not for TDX")]
+    UnsupportedTcbInfo,
+    #[error("FMSPC of the PCK certificate does not match TCB Info")]
+    FmspcMismatch,
+    #[error("PCE ID of the PCK certificate does not match TCB Info")]
+    PceIdMismatch,
+    #[error("no matching TCB level")]
+    NoMatchingTcbLevel,
+    #[error("TDX module does not match TCB Info")]
+    TdxModuleMismatch,
+    #[error("no matching TDX module TCB level")]
+    NoMatchingTdxModuleTcbLevel,
+    #[error("QE Identity is not for the TD Quoting Enclave")]
+    UnsupportedQeIdentity,
+    #[error("QE report does not match QE Identity")]
+    QeIdentityMismatch,
+    #[error("no matching QE TCB level")]
+    NoMatchingQeTcbLevel,
+    #[error("TCB status {0:?} is not accepted")]
+    StatusNotAccepted(TcbStatus),
+}
+
+/// Minimal DER reader, which yields the tag and content of consecutive elements
+struct Der<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    fn next(&mut self) -> Result<(u8, &'a [u8]), TcbError> {
+        let [tag, len, rest @ ..] = self.bytes else {
+            return Err(TcbError::InvalidDer);
+        };
+        let (len, rest) = match *len {
+            len @ 0..=0x7f => (len as usize, rest),
+            0x81..=0x84 => {
+                let num_bytes = (*len & 0x7f) as usize;
+                let (len_bytes, rest) = rest
+                    .split_at_checked(num_bytes)
+                    .ok_or(TcbError::InvalidDer)?;
+                let len = len_bytes
+                    .iter()
+                    .fold(0usize, |len, &b| (len << 8) | b as usize);
+                (len, rest)
+            }
+            _ => return Err(TcbError::InvalidDer),
+        };
+        let (content, rest) = rest.split_at_checked(len).ok_or(TcbError::InvalidDer)?;
+        self.bytes = rest;
+        Ok((*tag, content))
+    }
+
+    fn expect(&mut self, expected_tag: u8) -> Result<&'a [u8], TcbError> {
+        match self.next()? {
+            (tag, content) if tag == expected_tag => Ok(content),
+            _ => Err(TcbError::InvalidDer),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+fn der_integer(bytes: &[u8]) -> Result<u16, TcbError> {
+    if bytes.is_empty() || bytes.len() > 3 {
+        return Err(TcbError::InvalidDer);
+    }
+    let value = bytes.iter().fold(0u32, |value, &b| (value << 8) | b as u32);
+    u16::try_from(value).map_err(|_| TcbError::InvalidDer)
+}
+
+/// An entry of the SGX extension: the last arc of its OID, and the tag and content of its value
+type SgxExtensionEntry<'a> = (u8, u8, &'a [u8]);
+
+/// Iterate over a SEQUENCE of `SEQUENCE { OID, value }` in the SGX extension and return the
+/// entries whose OID is directly below `prefix`
+fn sgx_extension_entries<'a>(
+    bytes: &'a [u8],
+    prefix: &[u8],
+) -> Result<Vec<SgxExtensionEntry<'a>>, TcbError> {
+    let mut entries = vec![];
+    let mut der = Der { bytes };
+    while !der.is_empty() {
+        let mut entry = Der {
+            bytes: der.expect(DER_SEQUENCE)?,
+        };
+        let oid = entry.expect(DER_OID)?;
+        let (value_tag, value) = entry.next()?;
+        if let Some([arc]) = oid.strip_prefix(prefix) {
+            entries.push((*arc, value_tag, value));
+        }
+    }
+    Ok(entries)
+}
+
+/// TCB of a platform, as certified by its PCK certificate
+#[derive(Clone, Debug, PartialEq)]
+pub struct PckTcb {
+    pub comp_svn: [u8; 16],
+    pub pcesvn: u16,
+    pub cpusvn: [u8; 16],
+}
+
+/// The SGX extension of a PCK certificate
+#[derive(Clone, Debug, PartialEq)]
+pub struct PckExtension {
+    pub tcb: PckTcb,
+    pub pceid: [u8; 2],
+    pub fmspc: [u8; 6],
+}
+
+impl PckExtension {
+    /// Parse the SGX extension of a PCK certificate
+    pub fn from_cert(cert: &X509) -> Result<Self, TcbError> {
+        let der = cert.to_der()?;
+        let certificate = Der { bytes: &der }.expect(DER_SEQUENCE)?;
+        let mut tbs_certificate = Der {
+            bytes: Der { bytes: certificate }.expect(DER_SEQUENCE)?,
+        };
+
+        while !tbs_certificate.is_empty() {
+            let (tag, content) = tbs_certificate.next()?;
+            if tag != DER_EXTENSIONS {
+                continue;
+            }
+            let mut extensions = Der {
+                bytes: Der { bytes: content }.expect(DER_SEQUENCE)?,
+            };
+            while !extensions.is_empty() {
+                let mut extension = Der {
+                    bytes: extensions.expect(DER_SEQUENCE)?,
+                };
+                if extension.expect(DER_OID)? != SGX_EXTENSION_OID {
+                    continue;
+                }
+                let value = extension.expect(DER_OCTET_STRING)?;
+                let entries = Der { bytes: value }.expect(DER_SEQUENCE)?;
+                return Self::parse(entries);
+            }
+        }
+        Err(TcbError::MissingSgxExtension)
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self, TcbError> {
+        let mut tcb = None;
+        let mut pceid = None;
+        let mut fmspc = None;
+        for (arc, tag, value) in sgx_extension_entries(bytes, SGX_EXTENSION_OID)? {
+            match (arc, tag) {
+                (SGX_EXTENSION_TCB, DER_SEQUENCE) => tcb = Some(PckTcb::parse(value)?),
+                (SGX_EXTENSION_PCEID, DER_OCTET_STRING) => pceid = value.try_into().ok(),
+                (SGX_EXTENSION_FMSPC, DER_OCTET_STRING) => fmspc = value.try_into().ok(),
+                _ => {}
+            }
+        }
+        let extension = Self {
+            tcb: tcb.ok_or(TcbError::InvalidDer)?,
+            pceid: pceid.ok_or(TcbError::InvalidDer)?,
+            fmspc: fmspc.ok_or(TcbError::InvalidDer)?,
+        };
+        Ok(extension)
+    }
+}
+
+impl PckTcb {
+    fn parse(bytes: &[u8]) -> Result<Self, TcbError> {
+        let prefix = [SGX_EXTENSION_OID, &[SGX_EXTENSION_TCB]].concat();
+        let mut comp_svn = [None; 16];
+        let mut pcesvn = None;
+        let mut cpusvn = None;
+        for (arc, tag, value) in sgx_extension_entries(bytes, &prefix)? {
+            match (arc, tag) {
+                (1..=16, DER_INTEGER) => {
+                    let svn = der_integer(value)?;
+                    comp_svn[arc as usize - 1] =
+                        Some(u8::try_from(svn).map_err(|_| TcbError::InvalidDer)?);
+                }
+                (TCB_PCESVN, DER_INTEGER) => pcesvn = Some(der_integer(value)?),
+                (TCB_CPUSVN, DER_OCTET_STRING) => cpusvn = value.try_into().ok(),
+                _ => {}
+            }
+        }
+        let mut svns = [0; 16];
+        for (svn, parsed) in svns.iter_mut().zip(comp_svn) {
+            *svn = parsed.ok_or(TcbError::InvalidDer)?;
+        }
+        let tcb = Self {
+            comp_svn: svns,
+            pcesvn: pcesvn.ok_or(TcbError::InvalidDer)?,
+            cpusvn: cpusvn.ok_or(TcbError::InvalidDer)?,
+        };
+        Ok(tcb)
+    }
+}
+
+/// Verify that an issuer chain is rooted in the Intel SGX Root CA and that its leaf signed the
+/// given JSON. The chain gets the same checks as a PCK chain, i.e. the validity periods, the CA
+/// constraints and the revocation of the signing certificate.
+fn verify_signed_json(
+    json: &str,
+    signature: &[u8],
+    issuer_chain: &[X509],
+    root_ca_crl: &X509CrlRef,
+) -> Result<(), TcbError> {
+    let [signing_cert, root_ca] = issuer_chain else {
+        return Err(TcbError::IssuerChain);
+    };
+    let intel_root_ca = intel_root_ca()?;
+    if root_ca.to_der()? != intel_root_ca.to_der()?
+        || !signing_cert.verify(&*intel_root_ca.public_key()?)?
+    {
+        return Err(TcbError::IssuerChain);
+    }
+    let result = verify_chain(signing_cert, &[], intel_root_ca)?;
+    if result != X509VerifyResult::OK {
+        return Err(TcbError::InvalidIssuerChain(result));
+    }
+    check_crl(root_ca_crl, root_ca, signing_cert)?;
+
+    let signature: &[u8; 64] = signature.try_into().map_err(|_| TcbError::Signature)?;
+    let sig = ecdsa_sig(signature)?;
+    let pubkey = signing_cert.public_key()?.ec_key()?;
+    if !sig.verify(&sha256(json.as_bytes()), &pubkey)? {
+        return Err(TcbError::Signature);
+    }
+    Ok(())
+}
+
+impl SignedTcbInfo {
+    /// Verify the TCB Info's signature and issuer chain
+    ///
+    /// # Arguments
+    ///
+    /// * `root_ca_crl` - The CRL of the Intel SGX Root CA
+    pub fn verify(&self, root_ca_crl: &X509CrlRef) -> Result<(), TcbError> {
+        verify_signed_json(
+            &self.tcb_info_json,
+            &self.signature,
+            &self.issuer_chain,
+            root_ca_crl,
+        )
+    }
+}
+
+impl SignedQeIdentity {
+    /// Verify the QE Identity's signature and issuer chain
+    ///
+    /// # Arguments
+    ///
+    /// * `root_ca_crl` - The CRL of the Intel SGX Root CA
+    pub fn verify(&self, root_ca_crl: &X509CrlRef) -> Result<(), TcbError> {
+        verify_signed_json(
+            &self.qe_identity_json,
+            &self.signature,
+            &self.issuer_chain,
+            root_ca_crl,
+        )
+    }
+}
+
+/// Parse an ISO 8601 date of collateral, e.g. "2025-06-19T10:16:03Z"
+fn parse_date(date: &str) -> Result<Asn1Time, TcbError> {
+    let digits: String = date.chars().filter(char::is_ascii_digit).collect();
+    if digits.len() != 14 || !date.ends_with('Z') {
+        return Err(TcbError::InvalidDate(date.to_string()));
+    }
+    Asn1Time::from_str(&format!("{digits}Z")).map_err(|_| TcbError::InvalidDate(date.to_string()))
+}
+
+/// Check that collateral has been issued and has not expired at the given time
+fn check_dates(issue_date: &str, next_update: &str, now: &Asn1TimeRef) -> Result<(), TcbError> {
+    if *now < *parse_date(issue_date)? {
+        return Err(TcbError::CollateralNotYetIssued);
+    }
+    if *now > *parse_date(next_update)? {
+        return Err(TcbError::CollateralExpired);
+    }
+    Ok(())
+}
+
+fn severity(status: TcbStatus) -> u8 {
+    match status {
+        TcbStatus::UpToDate => 0,
+        TcbStatus::SWHardeningNeeded => 1,
+        TcbStatus::ConfigurationNeeded => 2,
+        TcbStatus::ConfigurationAndSWHardeningNeeded => 3,
+        TcbStatus::OutOfDate => 4,
+        TcbStatus::OutOfDateConfigurationNeeded => 5,
+        TcbStatus::Revoked => 6,
+    }
+}
+
+/// Result of a TCB evaluation, with a QVL-style status and the advisories that apply
+#[derive(Clone, Debug, PartialEq)]
+pub struct TcbEvaluation {
+    pub status: TcbStatus,
+    pub advisory_ids: Vec<String>,
+}
+
+impl TcbEvaluation {
+    fn new(status: TcbStatus, advisory_ids: &[String]) -> Self {
+        Self {
+            status,
+            advisory_ids: advisory_ids.to_vec(),
+        }
+    }
+
+    /// Converge the platform's status with the status of a component, i.e. the QE or the TDX
+    /// module
+    fn merge(mut self, component: TcbEvaluation) -> Self {
+        self.status = match (self.status, component.status) {
+            (
+                TcbStatus::ConfigurationNeeded | TcbStatus::ConfigurationAndSWHardeningNeeded,
+                TcbStatus::OutOfDate,
+            ) => TcbStatus::OutOfDateConfigurationNeeded,
+            (platform, component) => std::cmp::max_by_key(platform, component, |s| severity(*s)),
+        };
+        for advisory_id in component.advisory_ids {
+            if !self.advisory_ids.contains(&advisory_id) {
+                self.advisory_ids.push(advisory_id);
+            }
+        }
+        self
+    }
+}
+
+/// The TCB statuses a verifier accepts
+#[derive(Clone, Debug)]
+pub struct TcbPolicy {
+    pub accepted_statuses: Vec<TcbStatus>,
+}
+
+impl Default for TcbPolicy {
+    fn default() -> Self {
+        Self {
+            accepted_statuses: vec![TcbStatus::UpToDate],
+        }
+    }
+}
+
+impl TcbPolicy {
+    /// Check whether the status of a TCB evaluation is accepted
+    pub fn check(&self, evaluation: &TcbEvaluation) -> Result<(), TcbError> {
+        if !self.accepted_statuses.contains(&evaluation.status) {
+            return Err(TcbError::StatusNotAccepted(evaluation.status));
+        }
+        Ok(())
+    }
+}
+
+fn masked_eq(actual: &[u8], expected: &[u8], mask: &[u8]) -> bool {
+    actual.len() == expected.len()
+        && actual.len() == mask.len()
+        && actual
+            .iter()
+            .zip(expected)
+            .zip(mask)
+            .all(|((a, e), m)| a & m == e & m)
+}
+
+fn match_isv_tcb_level(levels: &[IsvTcbLevel], isvsvn: u16) -> Option<TcbEvaluation> {
+    levels
+        .iter()
+        .find(|level| isvsvn >= level.tcb.isvsvn)
+        .map(|level| TcbEvaluation::new(level.tcb_status, &level.advisory_ids))
+}
+
+fn evaluate_platform(
+    td_quote: &TdQuote,
+    pck_extension: &PckExtension,
+    tcb_info: &TcbInfo,
+) -> Result<TcbEvaluation, TcbError> {
+    if tcb_info.id != TDX_TCB_INFO_ID || tcb_info.version < TDX_TCB_INFO_MIN_VERSION {
+        return Err(TcbError::UnsupportedTcbInfo);
+    }
+    if !tcb_info
+        .fmspc
+        .eq_ignore_ascii_case(&hex::encode(pck_extension.fmspc))
+    {
+        return Err(TcbError::FmspcMismatch);
+    }
+    if !tcb_info
+        .pce_id
+        .eq_ignore_ascii_case(&hex::encode(pck_extension.pceid))
+    {
+        return Err(TcbError::PceIdMismatch);
+    }
+
+    let pck_tcb = &pck_extension.tcb;
+    let tee_tcb_svn = &td_quote.body.tee_tcb_svn;
+    // The TDX module's components are checked by `evaluate_tdx_module()` in this case
+    let skipped_components = match tee_tcb_svn[1] {
+        0 => 0,
+        _ => TDX_MODULE_TCB_COMPONENTS,
+    };
+    let level = tcb_info
+        .tcb_levels
+        .iter()
+        .find(|level| {
+            let sgx = &level.tcb.sgxtcbcomponents;
+            let tdx = &level.tcb.tdxtcbcomponents;
+            pck_tcb.pcesvn >= level.tcb.pcesvn
+                && sgx.len() == pck_tcb.comp_svn.len()
+                && sgx
+                    .iter()
+                    .zip(pck_tcb.comp_svn)
+                    .all(|(c, svn)| svn >= c.svn)
+                && tdx.len() == tee_tcb_svn.len()
+                && tdx
+                    .iter()
+                    .zip(tee_tcb_svn)
+                    .skip(skipped_components)
+                    .all(|(c, &svn)| svn >= c.svn)
+        })
+        .ok_or(TcbError::NoMatchingTcbLevel)?;
+    Ok(TcbEvaluation::new(level.tcb_status, &level.advisory_ids))
+}
+
+/// Check the TDX module against TCB Info. Returns the module's TCB status if TCB Info has
+/// specific TCB levels for the module's version.
+fn evaluate_tdx_module(
+    td_quote: &TdQuote,
+    tcb_info: &TcbInfo,
+) -> Result<Option<TcbEvaluation>, TcbError> {
+    let body = &td_quote.body;
+    let module_isvsvn = body.tee_tcb_svn[0];
+    let module_version = body.tee_tcb_svn[1];
+    let tdx_module = tcb_info
+        .tdx_module
+        .as_ref()
+        .ok_or(TcbError::UnsupportedTcbInfo)?;
+
+    let mut expected = (
+        &tdx_module.mrsigner,
+        &tdx_module.attributes,
+        &tdx_module.attributes_mask,
+    );
+    let mut levels = None;
+    if module_version > 0 && !tcb_info.tdx_module_identities.is_empty() {
+        let id = format!("TDX_{module_version:02X}");
+        let identity = tcb_info
+            .tdx_module_identities
+            .iter()
+            .find(|identity| identity.id.eq_ignore_ascii_case(&id))
+            .ok_or(TcbError::TdxModuleMismatch)?;
+        expected = (
+            &identity.mrsigner,
+            &identity.attributes,
+            &identity.attributes_mask,
+        );
+        levels = Some(&identity.tcb_levels);
+    }
+
+    let (mrsigner, attributes, attributes_mask) = expected;
+    if body.mrsignerseam[..] != mrsigner[..]
+        || !masked_eq(&body.seamattributes, attributes, attributes_mask)
+    {
+        return Err(TcbError::TdxModuleMismatch);
+    }
+
+    match levels {
+        Some(levels) => match_isv_tcb_level(levels, module_isvsvn.into())
+            .map(Some)
+            .ok_or(TcbError::NoMatchingTdxModuleTcbLevel),
+        None => Ok(None),
+    }
+}
+
+fn evaluate_qe(td_quote: &TdQuote, qe_identity: &QeIdentity) -> Result<TcbEvaluation, TcbError> {
+    if qe_identity.id != TDX_QE_IDENTITY_ID {
+        return Err(TcbError::UnsupportedQeIdentity);
+    }
+    let qe_report = &td_quote
+        .signature_data
+        .qe_report_certification_data
+        .qe_report;
+    if qe_report.mrsigner[..] != qe_identity.mrsigner[..]
+        || qe_report.isv_prod_id() != qe_identity.isvprodid
+        || !masked_eq(
+            &qe_report.miscselect().to_le_bytes(),
+            &qe_identity.miscselect,
+            &qe_identity.miscselect_mask,
+        )
+        || !masked_eq(
+            &qe_report.attributes,
+            &qe_identity.attributes,
+            &qe_identity.attributes_mask,
+        )
+    {
+        return Err(TcbError::QeIdentityMismatch);
+    }
+    match_isv_tcb_level(&qe_identity.tcb_levels, qe_report.isv_svn())
+        .ok_or(TcbError::NoMatchingQeTcbLevel)
+}
+
+/// Evaluate the TCB status of a verified TD quote. The collateral is verified, and rejected if
+/// its `nextUpdate` has passed. Then the platform's SVNs from the PCK certificate and the
+/// quote's `tee_tcb_svn` are matched against TCB Info, the QE report is matched against QE
+/// Identity, and the resulting statuses are converged.
+///
+/// # Arguments
+///
+/// * `td_quote` - A TD quote, verified with `TdQuote::verify_with_crls()`
+/// * `tcb_info` - The TCB Info of the quote's platform
+/// * `qe_identity` - The identity of the TDX Quoting Enclave
+/// * `root_ca_crl` - The CRL of the Intel SGX Root CA
+pub fn evaluate(
+    td_quote: &TdQuote,
+    tcb_info: &SignedTcbInfo,
+    qe_identity: &SignedQeIdentity,
+    root_ca_crl: &X509CrlRef,
+) -> Result<TcbEvaluation, TcbError> {
+    let now = Asn1Time::days_from_now(0)?;
+    evaluate_at(td_quote, tcb_info, qe_identity, root_ca_crl, &now)
+}
+
+fn evaluate_at(
+    td_quote: &TdQuote,
+    tcb_info: &SignedTcbInfo,
+    qe_identity: &SignedQeIdentity,
+    root_ca_crl: &X509CrlRef,
+    now: &Asn1TimeRef,
+) -> Result<TcbEvaluation, TcbError> {
+    tcb_info.verify(root_ca_crl)?;
+    qe_identity.verify(root_ca_crl)?;
+    let (tcb_info, qe_identity) = (&tcb_info.tcb_info, &qe_identity.qe_identity);
+    check_dates(&tcb_info.issue_date, &tcb_info.next_update, now)?;
+    check_dates(&qe_identity.issue_date, &qe_identity.next_update, now)?;
+    evaluate_collateral(td_quote, tcb_info, qe_identity)
+}
+
+fn evaluate_collateral(
+    td_quote: &TdQuote,
+    tcb_info: &TcbInfo,
+    qe_identity: &QeIdentity,
+) -> Result<TcbEvaluation, TcbError> {
+    let pck_chain = td_quote.pck_chain()?;
+    let pck_extension = PckExtension::from_cert(&pck_chain.pck)?;
+
+    let mut evaluation = evaluate_platform(td_quote, &pck_extension, tcb_info)?;
+    if let Some(tdx_module) = evaluate_tdx_module(td_quote, tcb_info)? {
+        evaluation = evaluation.merge(tdx_module);
+    }
+    let qe = evaluate_qe(td_quote, qe_identity)?;
+    Ok(evaluation.merge(qe))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::parse;
+    use openssl::x509::X509Crl;
+    use serde_json::Value;
+
+    fn crl(name: &str) -> X509Crl {
+        let bytes = include_bytes!("../test/tdx-collateral.json");
+        let collateral: Value = serde_json::from_slice(bytes).unwrap();
+        let der = hex::decode(collateral[name].as_str().unwrap()).unwrap();
+        X509Crl::from_der(&der).unwrap()
+    }
+
+    fn collateral() -> (SignedTcbInfo, SignedQeIdentity) {
+        let bytes = include_bytes!("../test/tdx-collateral.json");
+        let collateral: Value = serde_json::from_slice(bytes).unwrap();
+        let field = |name: &str| collateral[name].as_str().unwrap().to_string();
+        let tcb_info = SignedTcbInfo::parse(
+            format!(
+                r#"{{"tcbInfo":{},"signature":"{}"}}"#,
+                field("tcb_info"),
+                field("tcb_info_signature")
+            )
+            .as_bytes(),
+            field("tcb_info_issuer_chain").as_bytes(),
+        )
+        .unwrap();
+        let qe_identity = SignedQeIdentity::parse(
+            format!(
+                r#"{{"enclaveIdentity":{},"signature":"{}"}}"#,
+                field("qe_identity"),
+                field("qe_identity_signature")
+            )
+            .as_bytes(),
+            field("qe_identity_issuer_chain").as_bytes(),
+        )
+        .unwrap();
+        (tcb_info, qe_identity)
+    }
+
+    #[test]
+    fn parse_pck_extension() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let pck = td_quote.pck_chain().unwrap().pck;
+        let pck_extension = PckExtension::from_cert(&pck).unwrap();
+        assert_eq!(hex::encode_upper(pck_extension.fmspc), "B0C06F000000");
+        assert_eq!(pck_extension.pceid, [0, 0]);
+        assert_eq!(pck_extension.tcb.cpusvn, pck_extension.tcb.comp_svn);
+        assert_eq!(pck_extension.tcb.pcesvn, 11);
+
+        let root_ca = intel_root_ca().unwrap();
+        assert!(matches!(
+            PckExtension::from_cert(&root_ca),
+            Err(TcbError::MissingSgxExtension)
+        ));
+    }
+
+    #[test]
+    fn verify_collateral() {
+        let (tcb_info, qe_identity) = collateral();
+        let root_ca_crl = crl("root_ca_crl");
+        tcb_info.verify(&root_ca_crl).unwrap();
+        qe_identity.verify(&root_ca_crl).unwrap();
+
+        let mut tampered = tcb_info.clone();
+        tampered.tcb_info_json = tampered.tcb_info_json.replace("UpToDate", "OutOfDate");
+        assert!(matches!(
+            tampered.verify(&root_ca_crl),
+            Err(TcbError::Signature)
+        ));
+
+        let mut tampered = qe_identity.clone();
+        tampered.issuer_chain.pop();
+        assert!(matches!(
+            tampered.verify(&root_ca_crl),
+            Err(TcbError::IssuerChain)
+        ));
+
+        // the PCK CRL is not issued by the Intel SGX Root CA
+        assert!(matches!(
+            qe_identity.verify(&crl("pck_crl")),
+            Err(TcbError::Verify(VerifyError::CrlSignature))
+        ));
+    }
+
+    #[test]
+    fn evaluate_signed_collateral() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let (tcb_info, qe_identity) = collateral();
+        let root_ca_crl = crl("root_ca_crl");
+        let now = Asn1Time::from_str("20250701000000Z").unwrap();
+        let evaluation =
+            evaluate_at(&td_quote, &tcb_info, &qe_identity, &root_ca_crl, &now).unwrap();
+        assert_eq!(evaluation.status, TcbStatus::UpToDate);
+
+        let mut tampered = tcb_info.clone();
+        tampered.tcb_info_json = tampered.tcb_info_json.replace("UpToDate", "OutOfDate");
+        assert!(matches!(
+            evaluate_at(&td_quote, &tampered, &qe_identity, &root_ca_crl, &now),
+            Err(TcbError::Signature)
+        ));
+
+        let expired = Asn1Time::from_str("20250801000000Z").unwrap();
+        assert!(matches!(
+            evaluate_at(&td_quote, &tcb_info, &qe_identity, &root_ca_crl, &expired),
+            Err(TcbError::CollateralExpired)
+        ));
+        assert!(matches!(
+            evaluate(&td_quote, &tcb_info, &qe_identity, &root_ca_crl),
+            Err(TcbError::CollateralExpired)
+        ));
+
+        let not_yet_issued = Asn1Time::from_str("20250601000000Z").unwrap();
+        assert!(matches!(
+            evaluate_at(
+                &td_quote,
+                &tcb_info,
+                &qe_identity,
+                &root_ca_crl,
+                &not_yet_issued
+            ),
+            Err(TcbError::CollateralNotYetIssued)
+        ));
+
+        assert!(matches!(
+            parse_date("2025-07-19"),
+            Err(TcbError::InvalidDate(_))
+        ));
+    }
+
+    #[test]
+    fn evaluate_tcb_status() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let (tcb_info, qe_identity) = collateral();
+        let evaluation =
+            evaluate_collateral(&td_quote, &tcb_info.tcb_info, &qe_identity.qe_identity).unwrap();
+        assert_eq!(evaluation.status, TcbStatus::UpToDate);
+        assert!(evaluation.advisory_ids.is_empty());
+        TcbPolicy::default().check(&evaluation).unwrap();
+
+        let mut tcb_info = tcb_info.tcb_info;
+        tcb_info.tcb_levels[0].tcb.tdxtcbcomponents[2].svn = 0xff;
+        tcb_info.tcb_levels[1].advisory_ids = vec!["INTEL-SA-00000".into()];
+        let evaluation =
+            evaluate_collateral(&td_quote, &tcb_info, &qe_identity.qe_identity).unwrap();
+        assert_eq!(evaluation.status, TcbStatus::OutOfDate);
+        assert_eq!(evaluation.advisory_ids, ["INTEL-SA-00000"]);
+        assert!(matches!(
+            TcbPolicy::default().check(&evaluation),
+            Err(TcbError::StatusNotAccepted(TcbStatus::OutOfDate))
+        ));
+        let policy = TcbPolicy {
+            accepted_statuses: vec![TcbStatus::UpToDate, TcbStatus::OutOfDate],
+        };
+        policy.check(&evaluation).unwrap();
+
+        let mut qe_identity = qe_identity.qe_identity;
+        qe_identity.isvprodid += 1;
+        assert!(matches!(
+            evaluate_collateral(&td_quote, &tcb_info, &qe_identity),
+            Err(TcbError::QeIdentityMismatch)
+        ));
+    }
+
+    #[test]
+    fn check_collateral_applies() {
+        let td_quote = parse(include_bytes!("../test/td-quote.bin")).unwrap();
+        let (tcb_info, qe_identity) = collateral();
+        let (tcb_info, qe_identity) = (tcb_info.tcb_info, qe_identity.qe_identity);
+
+        // the TDX module's components are covered by its identity, since its version is non-zero
+        assert_ne!(td_quote.body.tee_tcb_svn[1], 0);
+        let mut module_tcb_info = tcb_info.clone();
+        module_tcb_info.tcb_levels[0].tcb.tdxtcbcomponents[0].svn = 0xff;
+        let evaluation = evaluate_collateral(&td_quote, &module_tcb_info, &qe_identity).unwrap();
+        assert_eq!(evaluation.status, TcbStatus::UpToDate);
+
+        let mut other_pce = tcb_info.clone();
+        other_pce.pce_id = "0100".into();
+        assert!(matches!(
+            evaluate_collateral(&td_quote, &other_pce, &qe_identity),
+            Err(TcbError::PceIdMismatch)
+        ));
+
+        let mut sgx_qe_identity = qe_identity;
+        sgx_qe_identity.id = "QE".into();
+        assert!(matches!(
+            evaluate_collateral(&td_quote, &tcb_info, &sgx_qe_identity),
+            Err(TcbError::UnsupportedQeIdentity)
+        ));
+    }
+
+    #[test]
+    fn merge_statuses() {
+        let platform = TcbEvaluation::new(TcbStatus::ConfigurationNeeded, &["A".into()]);
+        let qe = TcbEvaluation::new(TcbStatus::OutOfDate, &["A".into(), "B".into()]);
+        let merged = platform.merge(qe);
+        assert_eq!(merged.status, TcbStatus::OutOfDateConfigurationNeeded);
+        assert_eq!(merged.advisory_ids, ["A", "B"]);
+
+        let platform = TcbEvaluation::new(TcbStatus::SWHardeningNeeded, &[]);
+        let qe = TcbEvaluation::new(TcbStatus::Revoked, &[]);
+        assert_eq!(platform.merge(qe).status, TcbStatus::Revoked);
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#![no_main]
+
+use az_cvm_vtpm::hcl::{AttestationHeader, HclReport};
+use az_cvm_vtpm::tdx::TdReport;
+use libfuzzer_sys::fuzz_target;
+use sev::firmware::guest::AttestationReport as SnpReport;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = AttestationHeader::parse(data);
+    let Ok(hcl_report) = HclReport::new(data.to_vec()) else {
+        return;
+    };
+    let _ = hcl_report.report_type();
+    let _ = hcl_report.var_data_hash();
+    let _ = hcl_report.var_data_sha256();
+    let _ = hcl_report.var_data_matches_report_data();
+    let _ = hcl_report.ak_pub();
+    let _ = hcl_report.ek_pub();
+    let _ = hcl_report.runtime_claims();
+    let _ = TdReport::try_from(&hcl_report);
+    let _ = SnpReport::try_from(&hcl_report);
+});
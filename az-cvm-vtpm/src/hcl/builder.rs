@@ -0,0 +1,245 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{
+    AttestationHeader, AttestationReport, HclError, HwReport, IgvmHashType, IgvmRequestData,
+    RuntimeClaims, VarDataHash, VarDataKeys, HCL_REPORT_SIGNATURE, HCL_REQUEST_TYPE,
+    IGVM_REQUEST_DATA_VERSION, MAX_REPORT_SIZE, SNP_REPORT_TYPE, TDX_REPORT_TYPE,
+};
+use jsonwebkey::JsonWebKey;
+use std::mem::size_of;
+use zerocopy::byteorder::little_endian::U32;
+use zerocopy::AsBytes;
+
+const DEFAULT_HEADER_VERSION: u32 = 2;
+
+enum VarData {
+    Raw(Vec<u8>),
+    Keys(Vec<JsonWebKey>),
+    Claims(Box<RuntimeClaims>),
+}
+
+/// Builder for synthetic HCL reports, e.g. to be used in tests. The resulting bytes have the
+/// same layout as a HCL report that is read from the vTPM's NV index.
+///
+/// ```
+/// use az_cvm_vtpm::hcl::{HclReport, HclReportBuilder};
+/// use sev::firmware::guest::AttestationReport;
+///
+/// let bytes = HclReportBuilder::new(AttestationReport::default())
+///     .with_var_data(br#"{"keys":[]}"#.to_vec())
+///     .with_recomputed_report_data()
+///     .build()
+///     .unwrap();
+/// let hcl_report = HclReport::new(bytes).unwrap();
+/// assert!(hcl_report.var_data_matches_report_data().unwrap());
+/// ```
+pub struct HclReportBuilder {
+    hw_report: HwReport,
+    var_data: VarData,
+    version: u32,
+    request_type: u32,
+    status: u32,
+    hash_type: IgvmHashType,
+    recompute_report_data: bool,
+    size: usize,
+}
+
+impl HclReportBuilder {
+    /// Create a builder for a HCL report wrapping the given hardware report
+    pub fn new(hw_report: impl Into<HwReport>) -> Self {
+        Self {
+            hw_report: hw_report.into(),
+            var_data: VarData::Keys(vec![]),
+            version: DEFAULT_HEADER_VERSION,
+            request_type: HCL_REQUEST_TYPE,
+            status: 0,
+            hash_type: IgvmHashType::Sha256,
+            recompute_report_data: false,
+            size: 0,
+        }
+    }
+
+    /// Use a set of JWKs as VarData, serialized as `{"keys":[...]}`
+    pub fn with_keys(mut self, keys: Vec<JsonWebKey>) -> Self {
+        self.var_data = VarData::Keys(keys);
+        self
+    }
+
+    /// Use runtime claims as VarData
+    pub fn with_runtime_claims(mut self, claims: RuntimeClaims) -> Self {
+        self.var_data = VarData::Claims(Box::new(claims));
+        self
+    }
+
+    /// Use raw bytes as VarData
+    pub fn with_var_data(mut self, var_data: Vec<u8>) -> Self {
+        self.var_data = VarData::Raw(var_data);
+        self
+    }
+
+    /// Set the version field of the attestation header
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the request type field of the attestation header
+    pub fn with_request_type(mut self, request_type: u32) -> Self {
+        self.request_type = request_type;
+        self
+    }
+
+    /// Set the status field of the attestation header
+    pub fn with_status(mut self, status: u32) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Set the hash algorithm that binds the VarData to the hardware report
+    pub fn with_hash_type(mut self, hash_type: IgvmHashType) -> Self {
+        self.hash_type = hash_type;
+        self
+    }
+
+    /// Overwrite the hardware report's report data with the hash of the VarData, so the binding
+    /// stays valid. The remainder of the report data is zeroed. Note that this invalidates the
+    /// hardware report's signature.
+    pub fn with_recomputed_report_data(mut self) -> Self {
+        self.recompute_report_data = true;
+        self
+    }
+
+    /// Pad the report with zeros up to `size` bytes, like the vTPM's NV index does
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Serialize the HCL report
+    pub fn build(self) -> Result<Vec<u8>, HclError> {
+        let var_data = match self.var_data {
+            VarData::Raw(bytes) => bytes,
+            VarData::Keys(keys) => serde_json::to_vec(&VarDataKeys { keys })?,
+            VarData::Claims(claims) => serde_json::to_vec(&claims)?,
+        };
+
+        let mut hw_report = self.hw_report;
+        if self.recompute_report_data {
+            let var_data_hash = VarDataHash::compute(self.hash_type, &var_data)?;
+            let mut report_data = [0; 64];
+            report_data[..var_data_hash.as_bytes().len()].copy_from_slice(var_data_hash.as_bytes());
+            match hw_report {
+                HwReport::Tdx(ref mut td_report) => td_report.report_mac.reportdata = report_data,
+                HwReport::Snp(ref mut snp_report) => snp_report.report_data = report_data,
+            }
+        }
+
+        let (report_type, hw_report_bytes) = match hw_report {
+            HwReport::Tdx(td_report) => (TDX_REPORT_TYPE, td_report.as_bytes().to_vec()),
+            HwReport::Snp(snp_report) => (SNP_REPORT_TYPE, bincode::serialize(&snp_report)?),
+        };
+        let mut hw_report = [0; MAX_REPORT_SIZE];
+        hw_report[..hw_report_bytes.len()].copy_from_slice(&hw_report_bytes);
+
+        let variable_data_size = var_data.len() as u32;
+        let data_size = size_of::<IgvmRequestData>() as u32 + variable_data_size;
+        let report_size = size_of::<AttestationReport>() as u32 + variable_data_size;
+
+        let attestation_report = AttestationReport {
+            header: AttestationHeader {
+                signature: U32::new(HCL_REPORT_SIGNATURE),
+                version: U32::new(self.version),
+                report_size: U32::new(report_size),
+                request_type: U32::new(self.request_type),
+                status: U32::new(self.status),
+                reserved: [U32::ZERO; 3],
+            },
+            hw_report,
+            hcl_data: IgvmRequestData {
+                data_size: U32::new(data_size),
+                version: U32::new(IGVM_REQUEST_DATA_VERSION),
+                report_type: U32::new(report_type),
+                report_data_hash_type: U32::new(self.hash_type as u32),
+                variable_data_size: U32::new(variable_data_size),
+                variable_data: [],
+            },
+        };
+
+        let mut bytes = attestation_report.as_bytes().to_vec();
+        bytes.extend_from_slice(&var_data);
+        if bytes.len() < self.size {
+            bytes.resize(self.size, 0);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hcl::HclReport;
+    use crate::tdx::TdReport;
+    use sev::firmware::guest::AttestationReport as SnpReport;
+
+    #[test]
+    fn rebuild_snp_report() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let var_data = hcl_report.var_data().to_vec();
+        let snp_report: SnpReport = hcl_report.try_into().unwrap();
+
+        let rebuilt = HclReportBuilder::new(snp_report)
+            .with_version(1)
+            .with_var_data(var_data)
+            .with_size(bytes.len())
+            .build()
+            .unwrap();
+        assert_eq!(rebuilt, bytes);
+    }
+
+    #[test]
+    fn rebuild_tdx_report() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let var_data = hcl_report.var_data().to_vec();
+        let claims = hcl_report.runtime_claims().unwrap();
+        let td_report: TdReport = hcl_report.try_into().unwrap();
+
+        let rebuilt = HclReportBuilder::new(td_report)
+            .with_var_data(var_data)
+            .with_size(bytes.len())
+            .build()
+            .unwrap();
+        assert_eq!(rebuilt, bytes);
+
+        let bytes = HclReportBuilder::new(td_report)
+            .with_runtime_claims(claims.clone())
+            .build()
+            .unwrap();
+        let hcl_report = HclReport::new(bytes).unwrap();
+        assert_eq!(hcl_report.runtime_claims().unwrap(), claims);
+    }
+
+    #[test]
+    fn recompute_report_data() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let ak_pub = hcl_report.ak_pub().unwrap();
+        let td_report: TdReport = hcl_report.try_into().unwrap();
+
+        let bytes = HclReportBuilder::new(td_report)
+            .with_keys(vec![ak_pub.clone()])
+            .with_hash_type(IgvmHashType::Sha384)
+            .with_recomputed_report_data()
+            .build()
+            .unwrap();
+        let hcl_report = HclReport::new(bytes).unwrap();
+        assert_eq!(hcl_report.ak_pub().unwrap(), ak_pub);
+        assert!(matches!(
+            hcl_report.var_data_hash().unwrap(),
+            VarDataHash::Sha384(_)
+        ));
+        assert!(hcl_report.var_data_matches_report_data().unwrap());
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use jsonwebkey::JsonWebKey;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const HCL_AKPUB_KEY_ID: &str = "HCLAkPub";
+pub(crate) const HCL_EKPUB_KEY_ID: &str = "HCLEkPub";
+
+/// Runtime claims, as found in the VarData section of a HCL report
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeClaims {
+    pub keys: Vec<JsonWebKey>,
+    #[serde(rename = "vm-configuration")]
+    pub vm_configuration: VmConfiguration,
+    /// Hex-encoded data, provided by the guest when the HCL report was requested
+    #[serde(rename = "user-data", default, skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+}
+
+/// Configuration of the CVM, as attested by the HCL
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct VmConfiguration {
+    pub console_enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_cert_thumbprint: Option<String>,
+    pub secure_boot: bool,
+    pub tpm_enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tpm_persisted: Option<bool>,
+    #[serde(rename = "vmUniqueId")]
+    pub vm_unique_id: String,
+}
+
+pub(crate) fn find_key<'a>(keys: &'a [JsonWebKey], key_id: &str) -> Option<&'a JsonWebKey> {
+    keys.iter()
+        .find(|key| key.key_id.as_deref() == Some(key_id))
+}
+
+impl RuntimeClaims {
+    /// Get a JWK by its key id
+    pub fn key(&self, key_id: &str) -> Option<&JsonWebKey> {
+        find_key(&self.keys, key_id)
+    }
+
+    /// Get the vTPM's AKpub
+    pub fn ak_pub(&self) -> Option<&JsonWebKey> {
+        self.key(HCL_AKPUB_KEY_ID)
+    }
+
+    /// Get the vTPM's EKpub
+    pub fn ek_pub(&self) -> Option<&JsonWebKey> {
+        self.key(HCL_EKPUB_KEY_ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_runtime_claims() {
+        let bytes = include_bytes!("../../test/var-data.bin");
+        let claims: RuntimeClaims = serde_json::from_slice(bytes).unwrap();
+        assert!(claims.ak_pub().is_some());
+        assert!(claims.vm_configuration.secure_boot);
+        assert!(claims.vm_configuration.tpm_enabled);
+        assert_eq!(
+            claims.vm_configuration.vm_unique_id,
+            "3404EF27-32A2-4A07-A4C7-1A1171624C5D"
+        );
+        assert_eq!(claims.user_data.unwrap().len(), 128);
+    }
+}
@@ -0,0 +1,387 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{
+    HclError, HclReport, HclReportRef, IgvmHashType, ReportType, RuntimeClaims, SnpReport, TdReport,
+};
+use jsonwebkey::JsonWebKey;
+use serde::ser::Error as _;
+use serde::{Serialize, Serializer};
+use sev::firmware::host::TcbVersion;
+use std::fmt;
+
+// Offsets of the SNP report fields that the sev crate does not expose, see "SEV Secure Nested
+// Paging Firmware ABI Specification", Table 21
+const SNP_POLICY_OFFSET: usize = 0x08;
+const SNP_PLAT_INFO_OFFSET: usize = 0x40;
+const SNP_SIGNATURE_R_OFFSET: usize = 0x2a0;
+const SNP_SIGNATURE_S_OFFSET: usize = 0x2e8;
+const SNP_SIGNATURE_COMPONENT_SIZE: usize = 72;
+
+#[derive(Serialize)]
+struct HeaderJson {
+    signature: String,
+    version: u32,
+    report_size: u32,
+    request_type: u32,
+    status: u32,
+}
+
+#[derive(Serialize)]
+struct HclReportJson {
+    header: HeaderJson,
+    report_type: ReportType,
+    hw_report: HwReportJson,
+    var_data_hash_type: IgvmHashType,
+    var_data_hash: Option<String>,
+    runtime_claims: Option<RuntimeClaims>,
+    ak_pub: Option<JsonWebKey>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum HwReportJson {
+    Tdx(TdReportJson),
+    Snp(SnpReportJson),
+    #[serde(serialize_with = "hex::serialize")]
+    Raw(Vec<u8>),
+}
+
+#[derive(Serialize)]
+struct TdReportJson {
+    report_mac: ReportMacJson,
+    tee_tcb_info: TeeTcbInfoJson,
+    tdinfo: TdInfoJson,
+}
+
+#[derive(Serialize)]
+struct ReportMacJson {
+    report_type: u8,
+    report_subtype: u8,
+    report_version: u8,
+    #[serde(serialize_with = "hex::serialize")]
+    cpusvn: [u8; 16],
+    #[serde(serialize_with = "hex::serialize")]
+    tee_tcb_info_hash: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    tee_info_hash: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    reportdata: [u8; 64],
+    #[serde(serialize_with = "hex::serialize")]
+    mac: [u8; 32],
+}
+
+#[derive(Serialize)]
+struct TeeTcbInfoJson {
+    #[serde(serialize_with = "hex::serialize")]
+    valid: [u8; 8],
+    #[serde(serialize_with = "hex::serialize")]
+    tee_tcb_svn: [u8; 16],
+    #[serde(serialize_with = "hex::serialize")]
+    mrseam: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    mrsignerseam: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    attributes: [u8; 8],
+    #[serde(serialize_with = "hex::serialize")]
+    tee_tcb_svn2: [u8; 16],
+}
+
+#[derive(Serialize)]
+struct TdInfoJson {
+    #[serde(serialize_with = "hex::serialize")]
+    attributes: [u8; 8],
+    #[serde(serialize_with = "hex::serialize")]
+    xfam: [u8; 8],
+    #[serde(serialize_with = "hex::serialize")]
+    mrtd: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    mrconfigid: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    mrowner: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    mrownerconfig: [u8; 48],
+    rtmrs: Vec<String>,
+    #[serde(serialize_with = "hex::serialize")]
+    servtd_hash: [u8; 48],
+}
+
+impl From<&TdReport> for TdReportJson {
+    fn from(td_report: &TdReport) -> Self {
+        let report_mac = &td_report.report_mac;
+        let tee_tcb_info = &td_report.tee_tcb_info;
+        let tdinfo = &td_report.tdinfo;
+        Self {
+            report_mac: ReportMacJson {
+                report_type: report_mac.reporttype.r#type,
+                report_subtype: report_mac.reporttype.subtype,
+                report_version: report_mac.reporttype.version,
+                cpusvn: report_mac.cpusvn,
+                tee_tcb_info_hash: report_mac.tee_tcb_info_hash,
+                tee_info_hash: report_mac.tee_info_hash,
+                reportdata: report_mac.reportdata,
+                mac: report_mac.mac,
+            },
+            tee_tcb_info: TeeTcbInfoJson {
+                valid: tee_tcb_info.valid,
+                tee_tcb_svn: tee_tcb_info.tee_tcb_svn,
+                mrseam: tee_tcb_info.mrseam,
+                mrsignerseam: tee_tcb_info.mrsignerseam,
+                attributes: tee_tcb_info.attributes,
+                tee_tcb_svn2: tee_tcb_info.tee_tcb_svn2,
+            },
+            tdinfo: TdInfoJson {
+                attributes: tdinfo.attributes,
+                xfam: tdinfo.xfam,
+                mrtd: tdinfo.mrtd,
+                mrconfigid: tdinfo.mrconfigid,
+                mrowner: tdinfo.mrowner,
+                mrownerconfig: tdinfo.mrownerconfig,
+                rtmrs: tdinfo
+                    .rtrm
+                    .iter()
+                    .map(|rtmr| hex::encode(rtmr.register_data))
+                    .collect(),
+                servtd_hash: tdinfo.servtd_hash,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TcbVersionJson {
+    bootloader: u8,
+    tee: u8,
+    snp: u8,
+    microcode: u8,
+}
+
+impl From<&TcbVersion> for TcbVersionJson {
+    fn from(tcb: &TcbVersion) -> Self {
+        Self {
+            bootloader: tcb.bootloader,
+            tee: tcb.tee,
+            snp: tcb.snp,
+            microcode: tcb.microcode,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SnpSignatureJson {
+    #[serde(serialize_with = "hex::serialize")]
+    r: Vec<u8>,
+    #[serde(serialize_with = "hex::serialize")]
+    s: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct SnpReportJson {
+    version: u32,
+    guest_svn: u32,
+    policy: u64,
+    #[serde(serialize_with = "hex::serialize")]
+    family_id: [u8; 16],
+    #[serde(serialize_with = "hex::serialize")]
+    image_id: [u8; 16],
+    vmpl: u32,
+    sig_algo: u32,
+    current_tcb: TcbVersionJson,
+    plat_info: u64,
+    #[serde(serialize_with = "hex::serialize")]
+    report_data: [u8; 64],
+    #[serde(serialize_with = "hex::serialize")]
+    measurement: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    host_data: [u8; 32],
+    #[serde(serialize_with = "hex::serialize")]
+    id_key_digest: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    author_key_digest: [u8; 48],
+    #[serde(serialize_with = "hex::serialize")]
+    report_id: [u8; 32],
+    #[serde(serialize_with = "hex::serialize")]
+    report_id_ma: [u8; 32],
+    reported_tcb: TcbVersionJson,
+    #[serde(serialize_with = "hex::serialize")]
+    chip_id: [u8; 64],
+    committed_tcb: TcbVersionJson,
+    current_build: u8,
+    current_minor: u8,
+    current_major: u8,
+    committed_build: u8,
+    committed_minor: u8,
+    committed_major: u8,
+    launch_tcb: TcbVersionJson,
+    signature: SnpSignatureJson,
+}
+
+impl SnpReportJson {
+    fn new(snp_report: &SnpReport, bytes: &[u8]) -> Self {
+        let u64_at = |offset: usize| {
+            let mut field = [0u8; 8];
+            field.copy_from_slice(&bytes[offset..offset + 8]);
+            u64::from_le_bytes(field)
+        };
+        let bytes_at =
+            |offset: usize| bytes[offset..offset + SNP_SIGNATURE_COMPONENT_SIZE].to_vec();
+        Self {
+            version: snp_report.version,
+            guest_svn: snp_report.guest_svn,
+            policy: u64_at(SNP_POLICY_OFFSET),
+            family_id: snp_report.family_id,
+            image_id: snp_report.image_id,
+            vmpl: snp_report.vmpl,
+            sig_algo: snp_report.sig_algo,
+            current_tcb: (&snp_report.current_tcb).into(),
+            plat_info: u64_at(SNP_PLAT_INFO_OFFSET),
+            report_data: snp_report.report_data,
+            measurement: snp_report.measurement,
+            host_data: snp_report.host_data,
+            id_key_digest: snp_report.id_key_digest,
+            author_key_digest: snp_report.author_key_digest,
+            report_id: snp_report.report_id,
+            report_id_ma: snp_report.report_id_ma,
+            reported_tcb: (&snp_report.reported_tcb).into(),
+            chip_id: snp_report.chip_id,
+            committed_tcb: (&snp_report.committed_tcb).into(),
+            current_build: snp_report.current_build,
+            current_minor: snp_report.current_minor,
+            current_major: snp_report.current_major,
+            committed_build: snp_report.committed_build,
+            committed_minor: snp_report.committed_minor,
+            committed_major: snp_report.committed_major,
+            launch_tcb: (&snp_report.launch_tcb).into(),
+            signature: SnpSignatureJson {
+                r: bytes_at(SNP_SIGNATURE_R_OFFSET),
+                s: bytes_at(SNP_SIGNATURE_S_OFFSET),
+            },
+        }
+    }
+}
+
+impl TryFrom<&HclReportRef<'_>> for HclReportJson {
+    type Error = HclError;
+
+    fn try_from(hcl_report: &HclReportRef<'_>) -> Result<Self, Self::Error> {
+        let header = hcl_report.header();
+        let header = HeaderJson {
+            signature: String::from_utf8_lossy(&header.signature().to_le_bytes()).into_owned(),
+            version: header.version(),
+            report_size: header.report_size(),
+            request_type: header.request_type(),
+            status: header.status(),
+        };
+
+        let hw_report = match hcl_report.report_type() {
+            ReportType::Tdx => HwReportJson::Tdx((&TdReport::try_from(hcl_report)?).into()),
+            ReportType::Snp => HwReportJson::Snp(SnpReportJson::new(
+                &SnpReport::try_from(hcl_report)?,
+                hcl_report.hw_report(),
+            )),
+            ReportType::Tvm | ReportType::Vbs | ReportType::Reserved(_) => {
+                HwReportJson::Raw(hcl_report.hw_report().to_vec())
+            }
+        };
+
+        let json = Self {
+            header,
+            report_type: hcl_report.report_type(),
+            hw_report,
+            var_data_hash_type: hcl_report.var_data_hash_type(),
+            var_data_hash: hcl_report
+                .var_data_hash()
+                .ok()
+                .map(|h| hex::encode(h.as_bytes())),
+            runtime_claims: hcl_report.runtime_claims().ok(),
+            ak_pub: hcl_report.ak_pub().ok(),
+        };
+        Ok(json)
+    }
+}
+
+/// Serializes the report into a JSON document with the header fields, the hex-encoded hardware
+/// report and the decoded VarData claims.
+impl Serialize for HclReportRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = HclReportJson::try_from(self).map_err(S::Error::custom)?;
+        json.serialize(serializer)
+    }
+}
+
+impl Serialize for HclReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_report_ref().serialize(serializer)
+    }
+}
+
+impl fmt::Display for HclReportRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        write!(f, "{json}")
+    }
+}
+
+impl fmt::Display for HclReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_report_ref().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn serialize_hcl_report() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let json = serde_json::to_value(&hcl_report).unwrap();
+        assert_eq!(json["header"]["signature"], "HCLA");
+        assert_eq!(json["header"]["version"], 1);
+        assert_eq!(json["report_type"], "snp");
+        assert_eq!(
+            json["hw_report"]["report_data"],
+            hex::encode(hcl_report.report_data().unwrap())
+        );
+        let snp_report = SnpReport::try_from(&hcl_report).unwrap();
+        assert_eq!(json["hw_report"]["version"], snp_report.version);
+        assert_eq!(
+            json["hw_report"]["measurement"],
+            hex::encode(snp_report.measurement)
+        );
+        assert_eq!(
+            json["hw_report"]["reported_tcb"]["microcode"],
+            snp_report.reported_tcb.microcode
+        );
+        assert_eq!(
+            json["hw_report"]["signature"]["r"].as_str().unwrap().len(),
+            144
+        );
+        assert_eq!(json["var_data_hash_type"], "sha256");
+        assert_eq!(json["ak_pub"]["kid"], "HCLAkPub");
+        assert_eq!(
+            json["runtime_claims"]["vm-configuration"]["secure-boot"],
+            true
+        );
+
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let json = serde_json::to_value(&hcl_report).unwrap();
+        assert_eq!(json["report_type"], "tdx");
+        assert_eq!(
+            json["hw_report"]["report_mac"]["reportdata"],
+            hex::encode(hcl_report.report_data().unwrap())
+        );
+        let td_report = TdReport::try_from(&hcl_report).unwrap();
+        assert_eq!(
+            json["hw_report"]["tdinfo"]["rtmrs"][0],
+            hex::encode(td_report.tdinfo.rtrm[0].register_data)
+        );
+
+        let display = hcl_report.to_string();
+        let parsed: Value = serde_json::from_str(&display).unwrap();
+        assert_eq!(parsed, json);
+    }
+}
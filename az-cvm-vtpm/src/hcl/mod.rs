@@ -1,19 +1,35 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use crate::tdx::TdReport;
-use jsonwebkey::JsonWebKey;
+mod builder;
+mod claims;
+mod json;
+
+use crate::tdx::{TdReport, TdReportError};
+use claims::{find_key, HCL_AKPUB_KEY_ID, HCL_EKPUB_KEY_ID};
+use jsonwebkey::{JsonWebKey, Key};
 use memoffset::offset_of;
+#[cfg(feature = "verifier")]
+use openssl::{
+    bn::BigNum,
+    pkey::{PKey, Public},
+    rsa::Rsa,
+};
+#[cfg(feature = "verifier")]
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, RsaPublicKey};
 use serde::{Deserialize, Serialize};
-use serde_big_array::BigArray;
 use sev::firmware::guest::AttestationReport as SnpReport;
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha384, Sha512};
 use std::convert::TryFrom;
 use std::mem::size_of;
-use std::ops::Range;
 use thiserror::Error;
+use zerocopy::byteorder::little_endian::U32;
+use zerocopy::{AsBytes, FromBytes, FromZeroes, Unaligned};
+
+pub use builder::HclReportBuilder;
+pub use claims::{RuntimeClaims, VmConfiguration};
 
-const HCL_AKPUB_KEY_ID: &str = "HCLAkPub";
 const TD_REPORT_SIZE: usize = size_of::<TdReport>();
 const SNP_REPORT_SIZE: usize = size_of::<SnpReport>();
 const fn max(a: usize, b: usize) -> usize {
@@ -23,14 +39,19 @@ const fn max(a: usize, b: usize) -> usize {
     b
 }
 const MAX_REPORT_SIZE: usize = max(SNP_REPORT_SIZE, TD_REPORT_SIZE);
+const VBS_REPORT_TYPE: u32 = 1;
 const SNP_REPORT_TYPE: u32 = 2;
+const TVM_REPORT_TYPE: u32 = 3;
 const TDX_REPORT_TYPE: u32 = 4;
-const HW_REPORT_OFFSET: usize = offset_of!(AttestationReport, hw_report);
-const fn report_range(report_size: usize) -> Range<usize> {
-    HW_REPORT_OFFSET..(HW_REPORT_OFFSET + report_size)
-}
-const TD_REPORT_RANGE: Range<usize> = report_range(TD_REPORT_SIZE);
-const SNP_REPORT_RANGE: Range<usize> = report_range(SNP_REPORT_SIZE);
+const HCL_REPORT_SIGNATURE: u32 = u32::from_le_bytes(*b"HCLA");
+const HCL_REQUEST_TYPE: u32 = 2;
+const HCL_REPORT_VERSIONS: [u32; 2] = [1, 2];
+const HCL_REPORT_STATUS_SUCCESS: u32 = 0;
+const INVALID_REPORT_TYPE: u32 = 0;
+const IGVM_REQUEST_DATA_VERSION: u32 = 1;
+const RSA_PUBLIC_EXPONENT: u32 = 65537;
+const VAR_DATA_OFFSET: usize =
+    offset_of!(AttestationReport, hcl_data) + offset_of!(IgvmRequestData, variable_data);
 
 #[derive(Error, Debug)]
 pub enum HclError {
@@ -38,67 +59,191 @@ pub enum HclError {
     InvalidReportType,
     #[error("AkPub not found")]
     AkPubNotFound,
+    #[error("EkPub not found")]
+    EkPubNotFound,
+    #[error("invalid report signature {0:#010x}")]
+    InvalidSignature(u32),
+    #[error("unsupported report version {0}")]
+    UnsupportedVersion(u32),
+    #[error("report has failure status {0:#x}")]
+    FailureStatus(u32),
+    #[error("report size mismatch (expected {0} bytes, found {1})")]
+    ReportSizeMismatch(usize, usize),
+    #[error("truncated report (expected at least {0} bytes, found {1})")]
+    Truncated(usize, usize),
+    #[error("IGVM request data size {0} does not match VarData size {1}")]
+    InvalidDataSize(u32, u32),
+    #[error("VarData out of range")]
+    VarDataOutOfRange,
     #[error("binary parse error")]
     BinaryParseError(#[from] bincode::Error),
+    #[error("TD report error")]
+    TdReport(#[from] TdReportError),
     #[error("JSON parse error")]
     JsonParseError(#[from] serde_json::Error),
+    #[error("VarData hash does not match the hardware report's report data")]
+    BindingMismatch,
+    #[error("unused part of the hardware report's report data is not zeroed")]
+    NonZeroReportData,
+    #[error("unsupported key type, expected RSA")]
+    UnsupportedKeyType,
+    #[error("rsa error")]
+    Rsa(#[from] rsa::errors::Error),
+    #[cfg(feature = "verifier")]
+    #[error("openssl error")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
+    #[error("unsupported VarData hash type {0:?}")]
+    UnsupportedHashType(IgvmHashType),
+    #[error("invalid VarData hash type {0}")]
+    InvalidHashType(u32),
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 struct VarDataKeys {
     keys: Vec<JsonWebKey>,
 }
 
+/// Hash algorithm used to bind the VarData section to the hardware report
 #[repr(u32)]
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
-enum IgvmHashType {
+#[serde(rename_all = "lowercase")]
+pub enum IgvmHashType {
     Invalid = 0,
     Sha256,
     Sha384,
     Sha512,
 }
 
+impl TryFrom<u32> for IgvmHashType {
+    type Error = HclError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let hash_type = match value {
+            0 => IgvmHashType::Invalid,
+            1 => IgvmHashType::Sha256,
+            2 => IgvmHashType::Sha384,
+            3 => IgvmHashType::Sha512,
+            _ => return Err(HclError::InvalidHashType(value)),
+        };
+        Ok(hash_type)
+    }
+}
+
 #[repr(C)]
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(FromZeroes, FromBytes, AsBytes, Unaligned, Clone, Debug, PartialEq)]
 struct IgvmRequestData {
-    data_size: u32,
-    version: u32,
-    report_type: u32,
-    report_data_hash_type: IgvmHashType,
-    variable_data_size: u32,
+    data_size: U32,
+    version: U32,
+    report_type: U32,
+    report_data_hash_type: U32,
+    variable_data_size: U32,
     variable_data: [u8; 0],
 }
 
+/// Header of a HCL report
 #[repr(C)]
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-struct AttestationHeader {
-    signature: u32,
-    version: u32,
-    report_size: u32,
-    request_type: u32,
-    status: u32,
-    reserved: [u32; 3],
+#[derive(FromZeroes, FromBytes, AsBytes, Unaligned, Clone, Debug, PartialEq)]
+pub struct AttestationHeader {
+    signature: U32,
+    version: U32,
+    report_size: U32,
+    request_type: U32,
+    status: U32,
+    reserved: [U32; 3],
+}
+
+impl AttestationHeader {
+    /// Parse the header of a HCL report without validating it, e.g. to log the header of a
+    /// report that has been rejected by `HclReport::new()`.
+    pub fn parse(bytes: &[u8]) -> Result<&Self, HclError> {
+        let header_size = size_of::<Self>();
+        Self::ref_from_prefix(bytes).ok_or(HclError::Truncated(header_size, bytes.len()))
+    }
+
+    /// Get the magic signature, "HCLA" for valid reports
+    pub fn signature(&self) -> u32 {
+        self.signature.get()
+    }
+
+    /// Get the version of the report format
+    pub fn version(&self) -> u32 {
+        self.version.get()
+    }
+
+    /// Get the size of the report in bytes, including the VarData section
+    pub fn report_size(&self) -> u32 {
+        self.report_size.get()
+    }
+
+    /// Get the type of request that produced the report
+    pub fn request_type(&self) -> u32 {
+        self.request_type.get()
+    }
+
+    /// Get the status of the report, 0 on success
+    pub fn status(&self) -> u32 {
+        self.status.get()
+    }
+
+    fn validate(&self) -> Result<(), HclError> {
+        if self.signature() != HCL_REPORT_SIGNATURE {
+            return Err(HclError::InvalidSignature(self.signature()));
+        }
+        if !HCL_REPORT_VERSIONS.contains(&self.version()) {
+            return Err(HclError::UnsupportedVersion(self.version()));
+        }
+        if self.status() != HCL_REPORT_STATUS_SUCCESS {
+            return Err(HclError::FailureStatus(self.status()));
+        }
+        Ok(())
+    }
 }
 
 #[repr(C)]
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(FromZeroes, FromBytes, AsBytes, Unaligned, Clone, Debug, PartialEq)]
 struct AttestationReport {
     header: AttestationHeader,
-    #[serde(with = "BigArray")]
     hw_report: [u8; MAX_REPORT_SIZE],
     hcl_data: IgvmRequestData,
 }
 
+const _: () = assert!(size_of::<AttestationHeader>() == 32);
+const _: () = assert!(size_of::<IgvmRequestData>() == 20);
+const _: () = assert!(size_of::<AttestationReport>() == VAR_DATA_OFFSET);
+
+/// A parsed HCL report, owning the underlying bytes
 pub struct HclReport {
-    bytes: Vec<u8>,
     attestation_report: AttestationReport,
+    var_data: Vec<u8>,
+    report_type: ReportType,
+    hash_type: IgvmHashType,
+}
+
+/// A parsed HCL report, borrowing the underlying bytes
+#[derive(Copy, Clone, Debug)]
+pub struct HclReportRef<'a> {
+    attestation_report: &'a AttestationReport,
+    var_data: &'a [u8],
     report_type: ReportType,
+    hash_type: IgvmHashType,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+/// Type of the hardware report nested in a HCL report
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ReportType {
+    /// Intel TDX TD report
     Tdx,
+    /// AMD SEV-SNP attestation report
     Snp,
+    /// Trusted VM without a hardware TEE (e.g. Trusted Launch), there is no hardware report
+    Tvm,
+    /// Virtualization-based security, the hardware report layout is not interpreted
+    Vbs,
+    /// Report type that is reserved for future use, the hardware report layout is not
+    /// interpreted
+    Reserved(u32),
 }
 
 pub enum HwReport {
@@ -106,88 +251,428 @@ pub enum HwReport {
     Snp(SnpReport),
 }
 
-impl HclReport {
-    /// Parse a HCL report from a byte slice.
-    pub fn new(bytes: Vec<u8>) -> Result<Self, HclError> {
-        let attestation_report: AttestationReport = bincode::deserialize(&bytes)?;
-        let report_type = match attestation_report.hcl_data.report_type {
+impl From<TdReport> for HwReport {
+    fn from(td_report: TdReport) -> Self {
+        HwReport::Tdx(td_report)
+    }
+}
+
+impl From<SnpReport> for HwReport {
+    fn from(snp_report: SnpReport) -> Self {
+        HwReport::Snp(snp_report)
+    }
+}
+
+/// Digest of the VarData section
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VarDataHash {
+    Sha256([u8; 32]),
+    Sha384([u8; 48]),
+    Sha512([u8; 64]),
+}
+
+impl VarDataHash {
+    fn compute(hash_type: IgvmHashType, var_data: &[u8]) -> Result<Self, HclError> {
+        let hash = match hash_type {
+            IgvmHashType::Sha256 => VarDataHash::Sha256(Sha256::digest(var_data).into()),
+            IgvmHashType::Sha384 => VarDataHash::Sha384(Sha384::digest(var_data).into()),
+            IgvmHashType::Sha512 => VarDataHash::Sha512(Sha512::digest(var_data).into()),
+            hash_type => return Err(HclError::UnsupportedHashType(hash_type)),
+        };
+        Ok(hash)
+    }
+
+    /// Get the raw digest bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            VarDataHash::Sha256(hash) => hash,
+            VarDataHash::Sha384(hash) => hash,
+            VarDataHash::Sha512(hash) => hash,
+        }
+    }
+
+    /// Check whether the digest is equal to the leading bytes of a hardware report's report data
+    pub fn matches(&self, report_data: &[u8; 64]) -> bool {
+        let hash = self.as_bytes();
+        report_data[..hash.len()] == *hash
+    }
+}
+
+impl AsRef<[u8]> for VarDataHash {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'a> HclReportRef<'a> {
+    /// Parse a HCL report from a byte slice, without copying it.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, HclError> {
+        let attestation_report = AttestationReport::ref_from_prefix(bytes)
+            .ok_or(HclError::Truncated(VAR_DATA_OFFSET, bytes.len()))?;
+        attestation_report.header.validate()?;
+        check_bounds(attestation_report, bytes.len())?;
+
+        let hcl_data = &attestation_report.hcl_data;
+        let report_type = match hcl_data.report_type.get() {
             TDX_REPORT_TYPE => ReportType::Tdx,
             SNP_REPORT_TYPE => ReportType::Snp,
-            _ => return Err(HclError::InvalidReportType),
+            TVM_REPORT_TYPE => ReportType::Tvm,
+            VBS_REPORT_TYPE => ReportType::Vbs,
+            INVALID_REPORT_TYPE => return Err(HclError::InvalidReportType),
+            report_type => ReportType::Reserved(report_type),
         };
+        let hash_type = hcl_data.report_data_hash_type.get().try_into()?;
+
+        let var_data_end = VAR_DATA_OFFSET + hcl_data.variable_data_size.get() as usize;
+        let var_data = &bytes[VAR_DATA_OFFSET..var_data_end];
 
         let report = Self {
-            bytes,
             attestation_report,
+            var_data,
             report_type,
+            hash_type,
         };
         Ok(report)
     }
 
+    /// Get the header of the report
+    pub fn header(&self) -> &'a AttestationHeader {
+        &self.attestation_report.header
+    }
+
     /// Get the type of the nested hardware report
     pub fn report_type(&self) -> ReportType {
         self.report_type
     }
 
-    fn report_slice(&self) -> &[u8] {
+    /// Get the bytes of the nested hardware report. For report types without a known hardware
+    /// report layout, the whole hardware report area is returned.
+    pub fn hw_report(&self) -> &'a [u8] {
+        let hw_report = &self.attestation_report.hw_report;
         match self.report_type {
-            ReportType::Tdx => self.bytes[TD_REPORT_RANGE].as_ref(),
-            ReportType::Snp => self.bytes[SNP_REPORT_RANGE].as_ref(),
+            ReportType::Tdx => &hw_report[..TD_REPORT_SIZE],
+            ReportType::Snp => &hw_report[..SNP_REPORT_SIZE],
+            ReportType::Tvm | ReportType::Vbs | ReportType::Reserved(_) => hw_report,
         }
     }
 
-    /// Get the SHA256 hash of the VarData section
+    /// Get the VarData section
+    pub fn var_data(&self) -> &'a [u8] {
+        self.var_data
+    }
+
+    /// Get the hash algorithm used to bind the VarData section to the hardware report
+    pub fn var_data_hash_type(&self) -> IgvmHashType {
+        self.hash_type
+    }
+
+    /// Get the hash of the VarData section, using the algorithm specified in the HCL report
+    pub fn var_data_hash(&self) -> Result<VarDataHash, HclError> {
+        VarDataHash::compute(self.hash_type, self.var_data)
+    }
+
+    /// Get the SHA256 hash of the VarData section, regardless of the hash type specified in the
+    /// HCL report. Use `var_data_hash()` to get the hash that is bound to the hardware report.
     pub fn var_data_sha256(&self) -> [u8; 32] {
-        if self.attestation_report.hcl_data.report_data_hash_type != IgvmHashType::Sha256 {
-            unimplemented!(
-                "Only SHA256 is supported, got {:?}",
-                self.attestation_report.hcl_data.report_data_hash_type
-            );
+        Sha256::digest(self.var_data).into()
+    }
+
+    /// Get the report data field of the nested hardware report
+    pub fn report_data(&self) -> Result<[u8; 64], HclError> {
+        let report_data = match self.report_type {
+            ReportType::Tdx => TdReport::try_from(self)?.report_mac.reportdata,
+            ReportType::Snp => SnpReport::try_from(self)?.report_data,
+            ReportType::Tvm | ReportType::Vbs | ReportType::Reserved(_) => {
+                return Err(HclError::InvalidReportType)
+            }
+        };
+        Ok(report_data)
+    }
+
+    /// Check whether the hash of the VarData section matches the corresponding prefix of the
+    /// hardware report's report data
+    pub fn var_data_matches_report_data(&self) -> Result<bool, HclError> {
+        let var_data_hash = self.var_data_hash()?;
+        let report_data = self.report_data()?;
+        Ok(var_data_hash.matches(&report_data))
+    }
+
+    /// Verify that the VarData section is bound to the hardware report, i.e. that its hash
+    /// matches the corresponding prefix of the hardware report's report data
+    pub fn verify_binding(&self) -> Result<(), HclError> {
+        self.verify_binding_inner(false)
+    }
+
+    /// Verify that the VarData section is bound to the hardware report, like
+    /// `verify_binding()`, and additionally require the remainder of the report data to be zero
+    pub fn verify_binding_zero_padded(&self) -> Result<(), HclError> {
+        self.verify_binding_inner(true)
+    }
+
+    fn verify_binding_inner(&self, require_zero_padding: bool) -> Result<(), HclError> {
+        let var_data_hash = self.var_data_hash()?;
+        let report_data = self.report_data()?;
+        if !var_data_hash.matches(&report_data) {
+            return Err(HclError::BindingMismatch);
+        }
+        let padding = &report_data[var_data_hash.as_bytes().len()..];
+        if require_zero_padding && padding.iter().any(|&b| b != 0) {
+            return Err(HclError::NonZeroReportData);
+        }
+        Ok(())
+    }
+
+    /// Get the vTPM's AKpub from the VarData section
+    pub fn ak_pub(&self) -> Result<JsonWebKey, HclError> {
+        let VarDataKeys { keys } = serde_json::from_slice(self.var_data)?;
+        let ak_pub = find_key(&keys, HCL_AKPUB_KEY_ID).ok_or(HclError::AkPubNotFound)?;
+        Ok(ak_pub.clone())
+    }
+
+    /// Get the vTPM's EKpub from the VarData section
+    pub fn ek_pub(&self) -> Result<JsonWebKey, HclError> {
+        let VarDataKeys { keys } = serde_json::from_slice(self.var_data)?;
+        let ek_pub = find_key(&keys, HCL_EKPUB_KEY_ID).ok_or(HclError::EkPubNotFound)?;
+        Ok(ek_pub.clone())
+    }
+
+    /// Parse the runtime claims from the VarData section
+    pub fn runtime_claims(&self) -> Result<RuntimeClaims, HclError> {
+        let claims = serde_json::from_slice(self.var_data)?;
+        Ok(claims)
+    }
+
+    /// Get the vTPM's AKpub from the VarData section as RSA public key
+    pub fn ak_pub_rsa(&self) -> Result<RsaPublicKey, HclError> {
+        rsa_public_key(&self.ak_pub()?)
+    }
+
+    /// Get the vTPM's EKpub from the VarData section as RSA public key
+    pub fn ek_pub_rsa(&self) -> Result<RsaPublicKey, HclError> {
+        rsa_public_key(&self.ek_pub()?)
+    }
+
+    /// Get the vTPM's AKpub from the VarData section as OpenSSL public key, e.g. to verify a
+    /// vTPM quote
+    #[cfg(feature = "verifier")]
+    pub fn ak_pub_pkey(&self) -> Result<PKey<Public>, HclError> {
+        openssl_public_key(&self.ak_pub_rsa()?)
+    }
+
+    /// Get the vTPM's EKpub from the VarData section as OpenSSL public key
+    #[cfg(feature = "verifier")]
+    pub fn ek_pub_pkey(&self) -> Result<PKey<Public>, HclError> {
+        openssl_public_key(&self.ek_pub_rsa()?)
+    }
+
+    /// Check whether the AKpub in the VarData section is equal to a given key, e.g. the one
+    /// returned by `vtpm::get_ak_pub()`
+    pub fn ak_pub_matches(&self, ak_pub: &RsaPublicKey) -> Result<bool, HclError> {
+        Ok(self.ak_pub_rsa()? == *ak_pub)
+    }
+}
+
+impl HclReport {
+    /// Parse a HCL report from a byte slice.
+    pub fn new(bytes: Vec<u8>) -> Result<Self, HclError> {
+        let report_ref = HclReportRef::new(&bytes)?;
+        let report = Self {
+            attestation_report: report_ref.attestation_report.clone(),
+            var_data: report_ref.var_data.to_vec(),
+            report_type: report_ref.report_type,
+            hash_type: report_ref.hash_type,
+        };
+        Ok(report)
+    }
+
+    /// Get a borrowed view of the report
+    pub fn as_report_ref(&self) -> HclReportRef<'_> {
+        // The report has already been validated in `new()`
+        HclReportRef {
+            attestation_report: &self.attestation_report,
+            var_data: &self.var_data,
+            report_type: self.report_type,
+            hash_type: self.hash_type,
         }
-        let mut hasher = Sha256::new();
-        hasher.update(self.var_data_slice());
-        let hash = hasher.finalize();
-        hash.into()
     }
 
-    /// Get the slice of the VarData section
-    fn var_data_slice(&self) -> &[u8] {
-        let var_data_offset =
-            offset_of!(AttestationReport, hcl_data) + offset_of!(IgvmRequestData, variable_data);
-        let hcl_data = &self.attestation_report.hcl_data;
-        let var_data_end = var_data_offset + hcl_data.variable_data_size as usize;
-        &self.bytes[var_data_offset..var_data_end]
+    /// Get the header of the report
+    pub fn header(&self) -> &AttestationHeader {
+        &self.attestation_report.header
+    }
+
+    /// Get the type of the nested hardware report
+    pub fn report_type(&self) -> ReportType {
+        self.report_type
+    }
+
+    /// Get the bytes of the nested hardware report
+    pub fn hw_report(&self) -> &[u8] {
+        self.as_report_ref().hw_report()
+    }
+
+    /// Get the VarData section
+    pub fn var_data(&self) -> &[u8] {
+        &self.var_data
+    }
+
+    /// Get the hash algorithm used to bind the VarData section to the hardware report
+    pub fn var_data_hash_type(&self) -> IgvmHashType {
+        self.hash_type
+    }
+
+    /// Get the hash of the VarData section, using the algorithm specified in the HCL report
+    pub fn var_data_hash(&self) -> Result<VarDataHash, HclError> {
+        self.as_report_ref().var_data_hash()
+    }
+
+    /// Get the SHA256 hash of the VarData section, regardless of the hash type specified in the
+    /// HCL report. Use `var_data_hash()` to get the hash that is bound to the hardware report.
+    pub fn var_data_sha256(&self) -> [u8; 32] {
+        self.as_report_ref().var_data_sha256()
+    }
+
+    /// Get the report data field of the nested hardware report
+    pub fn report_data(&self) -> Result<[u8; 64], HclError> {
+        self.as_report_ref().report_data()
+    }
+
+    /// Check whether the hash of the VarData section matches the corresponding prefix of the
+    /// hardware report's report data
+    pub fn var_data_matches_report_data(&self) -> Result<bool, HclError> {
+        self.as_report_ref().var_data_matches_report_data()
+    }
+
+    /// Verify that the VarData section is bound to the hardware report, i.e. that its hash
+    /// matches the corresponding prefix of the hardware report's report data
+    pub fn verify_binding(&self) -> Result<(), HclError> {
+        self.as_report_ref().verify_binding()
+    }
+
+    /// Verify that the VarData section is bound to the hardware report, like
+    /// `verify_binding()`, and additionally require the remainder of the report data to be zero
+    pub fn verify_binding_zero_padded(&self) -> Result<(), HclError> {
+        self.as_report_ref().verify_binding_zero_padded()
     }
 
     /// Get the vTPM's AKpub from the VarData section
     pub fn ak_pub(&self) -> Result<JsonWebKey, HclError> {
-        let VarDataKeys { keys } = serde_json::from_slice(self.var_data_slice())?;
-        let ak_pub = keys
-            .into_iter()
-            .find(|key| {
-                let Some(ref key_id) = key.key_id else {
-                    return false;
-                };
-                key_id == HCL_AKPUB_KEY_ID
-            })
-            .ok_or(HclError::AkPubNotFound)?;
-        Ok(ak_pub)
+        self.as_report_ref().ak_pub()
+    }
+
+    /// Get the vTPM's EKpub from the VarData section
+    pub fn ek_pub(&self) -> Result<JsonWebKey, HclError> {
+        self.as_report_ref().ek_pub()
+    }
+
+    /// Parse the runtime claims from the VarData section
+    pub fn runtime_claims(&self) -> Result<RuntimeClaims, HclError> {
+        self.as_report_ref().runtime_claims()
+    }
+
+    /// Get the vTPM's AKpub from the VarData section as RSA public key
+    pub fn ak_pub_rsa(&self) -> Result<RsaPublicKey, HclError> {
+        self.as_report_ref().ak_pub_rsa()
+    }
+
+    /// Get the vTPM's EKpub from the VarData section as RSA public key
+    pub fn ek_pub_rsa(&self) -> Result<RsaPublicKey, HclError> {
+        self.as_report_ref().ek_pub_rsa()
+    }
+
+    /// Get the vTPM's AKpub from the VarData section as OpenSSL public key, e.g. to verify a
+    /// vTPM quote
+    #[cfg(feature = "verifier")]
+    pub fn ak_pub_pkey(&self) -> Result<PKey<Public>, HclError> {
+        self.as_report_ref().ak_pub_pkey()
+    }
+
+    /// Get the vTPM's EKpub from the VarData section as OpenSSL public key
+    #[cfg(feature = "verifier")]
+    pub fn ek_pub_pkey(&self) -> Result<PKey<Public>, HclError> {
+        self.as_report_ref().ek_pub_pkey()
+    }
+
+    /// Check whether the AKpub in the VarData section is equal to a given key, e.g. the one
+    /// returned by `vtpm::get_ak_pub()`
+    pub fn ak_pub_matches(&self, ak_pub: &RsaPublicKey) -> Result<bool, HclError> {
+        self.as_report_ref().ak_pub_matches(ak_pub)
     }
 }
 
-impl TryFrom<&HclReport> for TdReport {
+/// Convert an RSA JWK into a public key
+fn rsa_public_key(jwk: &JsonWebKey) -> Result<RsaPublicKey, HclError> {
+    let Key::RSA { ref public, .. } = *jwk.key else {
+        return Err(HclError::UnsupportedKeyType);
+    };
+    let n = BigUint::from_bytes_be(&public.n);
+    let e = BigUint::from(RSA_PUBLIC_EXPONENT);
+    let pkey = RsaPublicKey::new(n, e)?;
+    Ok(pkey)
+}
+
+#[cfg(feature = "verifier")]
+fn openssl_public_key(rsa_pk: &RsaPublicKey) -> Result<PKey<Public>, HclError> {
+    let n = BigNum::from_slice(&rsa_pk.n().to_bytes_be())?;
+    let e = BigNum::from_slice(&rsa_pk.e().to_bytes_be())?;
+    let rsa = Rsa::from_public_components(n, e)?;
+    let pkey = PKey::from_rsa(rsa)?;
+    Ok(pkey)
+}
+
+/// Check the lengths in the report's header and IGVM request data against the buffer size
+fn check_bounds(attestation_report: &AttestationReport, len: usize) -> Result<(), HclError> {
+    let AttestationReport {
+        header, hcl_data, ..
+    } = attestation_report;
+
+    let report_size = header.report_size() as usize;
+    if report_size > len {
+        return Err(HclError::Truncated(report_size, len));
+    }
+
+    let data_size = hcl_data.data_size.get();
+    let variable_data_size = hcl_data.variable_data_size.get();
+    let var_data_size = variable_data_size as usize;
+    let expected_data_size = size_of::<IgvmRequestData>() + var_data_size;
+    if data_size as usize != expected_data_size {
+        return Err(HclError::InvalidDataSize(data_size, variable_data_size));
+    }
+
+    if var_data_size > len - VAR_DATA_OFFSET {
+        return Err(HclError::VarDataOutOfRange);
+    }
+
+    let expected_report_size = VAR_DATA_OFFSET + var_data_size;
+    if report_size != expected_report_size {
+        return Err(HclError::ReportSizeMismatch(
+            expected_report_size,
+            report_size,
+        ));
+    }
+    Ok(())
+}
+
+impl TryFrom<&HclReportRef<'_>> for TdReport {
     type Error = HclError;
 
-    fn try_from(hcl_report: &HclReport) -> Result<Self, Self::Error> {
+    fn try_from(hcl_report: &HclReportRef<'_>) -> Result<Self, Self::Error> {
         if hcl_report.report_type != ReportType::Tdx {
             return Err(HclError::InvalidReportType);
         }
-        let bytes = hcl_report.report_slice();
-        let td_report = bincode::deserialize::<TdReport>(bytes)?;
+        let td_report = TdReport::from_bytes(hcl_report.hw_report())?;
         Ok(td_report)
     }
 }
 
+impl TryFrom<&HclReport> for TdReport {
+    type Error = HclError;
+
+    fn try_from(hcl_report: &HclReport) -> Result<Self, Self::Error> {
+        (&hcl_report.as_report_ref()).try_into()
+    }
+}
+
 impl TryFrom<HclReport> for TdReport {
     type Error = HclError;
 
@@ -196,19 +681,26 @@ impl TryFrom<HclReport> for TdReport {
     }
 }
 
-impl TryFrom<&HclReport> for SnpReport {
+impl TryFrom<&HclReportRef<'_>> for SnpReport {
     type Error = HclError;
 
-    fn try_from(hcl_report: &HclReport) -> Result<Self, Self::Error> {
+    fn try_from(hcl_report: &HclReportRef<'_>) -> Result<Self, Self::Error> {
         if hcl_report.report_type != ReportType::Snp {
             return Err(HclError::InvalidReportType);
         }
-        let bytes = hcl_report.report_slice();
-        let snp_report = bincode::deserialize::<SnpReport>(bytes)?;
+        let snp_report = bincode::deserialize::<SnpReport>(hcl_report.hw_report())?;
         Ok(snp_report)
     }
 }
 
+impl TryFrom<&HclReport> for SnpReport {
+    type Error = HclError;
+
+    fn try_from(hcl_report: &HclReport) -> Result<Self, Self::Error> {
+        (&hcl_report.as_report_ref()).try_into()
+    }
+}
+
 impl TryFrom<HclReport> for SnpReport {
     type Error = HclError;
 
@@ -230,5 +722,283 @@ mod tests {
         let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
         let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
         let _ = hcl_report.ak_pub().unwrap();
+        let _ = hcl_report.ek_pub().unwrap();
+    }
+
+    #[test]
+    fn convert_keys() {
+        use rsa::pkcs8::DecodePublicKey;
+
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+
+        let der = hcl_report.ak_pub().unwrap().key.try_to_der().unwrap();
+        let ak_pub = RsaPublicKey::from_public_key_der(&der).unwrap();
+        assert_eq!(hcl_report.ak_pub_rsa().unwrap(), ak_pub);
+        assert!(hcl_report.ak_pub_matches(&ak_pub).unwrap());
+
+        let ek_pub = hcl_report.ek_pub_rsa().unwrap();
+        assert_ne!(ek_pub, ak_pub);
+        assert!(!hcl_report.ak_pub_matches(&ek_pub).unwrap());
+
+        #[cfg(feature = "verifier")]
+        {
+            let pkey = hcl_report.ak_pub_pkey().unwrap();
+            assert_eq!(pkey.public_key_to_der().unwrap(), der);
+        }
+    }
+
+    #[test]
+    fn parse_runtime_claims() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let claims = hcl_report.runtime_claims().unwrap();
+        assert_eq!(claims.ak_pub(), Some(&hcl_report.ak_pub().unwrap()));
+        assert_eq!(claims.ek_pub(), Some(&hcl_report.ek_pub().unwrap()));
+
+        let vm_config = &claims.vm_configuration;
+        assert!(vm_config.console_enabled);
+        assert!(!vm_config.secure_boot);
+        assert!(vm_config.tpm_enabled);
+        assert_eq!(vm_config.tpm_persisted, Some(false));
+        assert_eq!(
+            vm_config.vm_unique_id,
+            "D270E56B-F668-4990-A5BC-9B624576841D"
+        );
+        assert!(claims.user_data.is_some());
+
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let claims = hcl_report.runtime_claims().unwrap();
+        assert!(claims.ek_pub().is_none());
+        assert_eq!(claims.vm_configuration.current_time, Some(1678652405));
+        assert!(claims.user_data.is_none());
+    }
+
+    #[test]
+    fn parse_hcl_report_ref() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+
+        // parse from an unaligned slice
+        let mut unaligned = vec![0];
+        unaligned.extend_from_slice(bytes);
+        let hcl_report_ref = HclReportRef::new(&unaligned[1..]).unwrap();
+
+        assert_eq!(hcl_report_ref.report_type(), ReportType::Tdx);
+        assert_eq!(hcl_report_ref.header(), hcl_report.header());
+        assert_eq!(hcl_report_ref.hw_report(), hcl_report.hw_report());
+        assert_eq!(hcl_report_ref.var_data(), hcl_report.var_data());
+        assert_eq!(
+            hcl_report_ref.ak_pub().unwrap(),
+            hcl_report.ak_pub().unwrap()
+        );
+        assert!(hcl_report_ref.var_data_matches_report_data().unwrap());
+
+        let td_report = TdReport::try_from(&hcl_report_ref).unwrap();
+        assert_eq!(td_report, TdReport::try_from(&hcl_report).unwrap());
+        assert!(SnpReport::try_from(&hcl_report_ref).is_err());
+    }
+
+    #[test]
+    fn parse_tvm_report() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let report_type_offset =
+            offset_of!(AttestationReport, hcl_data) + offset_of!(IgvmRequestData, report_type);
+        let mut tvm_bytes = bytes.to_vec();
+        tvm_bytes[report_type_offset] = TVM_REPORT_TYPE as u8;
+
+        let hcl_report = HclReport::new(tvm_bytes).unwrap();
+        assert_eq!(hcl_report.report_type(), ReportType::Tvm);
+        assert_eq!(hcl_report.hw_report().len(), MAX_REPORT_SIZE);
+        let _ = hcl_report.ak_pub().unwrap();
+        let _ = hcl_report.runtime_claims().unwrap();
+        assert!(matches!(
+            hcl_report.report_data(),
+            Err(HclError::InvalidReportType)
+        ));
+        assert!(TdReport::try_from(&hcl_report).is_err());
+
+        let mut reserved_bytes = bytes.to_vec();
+        reserved_bytes[report_type_offset] = 5;
+        let hcl_report = HclReport::new(reserved_bytes).unwrap();
+        assert_eq!(hcl_report.report_type(), ReportType::Reserved(5));
+        assert_eq!(hcl_report.hw_report().len(), MAX_REPORT_SIZE);
+        let _ = hcl_report.ak_pub().unwrap();
+
+        let mut invalid_bytes = bytes.to_vec();
+        invalid_bytes[report_type_offset] = INVALID_REPORT_TYPE as u8;
+        let result = HclReport::new(invalid_bytes);
+        assert!(matches!(result, Err(HclError::InvalidReportType)));
+    }
+
+    #[test]
+    fn header_fields() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let header = hcl_report.header();
+        assert_eq!(header.signature().to_le_bytes(), *b"HCLA");
+        assert_eq!(header.version(), 1);
+        assert_eq!(header.report_size(), 1819);
+        assert_eq!(header.request_type(), HCL_REQUEST_TYPE);
+        assert_eq!(header.status(), 0);
+
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let header = AttestationHeader::parse(bytes).unwrap();
+        assert_eq!(header.version(), 2);
+        assert_eq!(header.report_size(), 2438);
+    }
+
+    #[test]
+    fn reject_invalid_headers() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let snp_report: SnpReport = HclReport::new(bytes.to_vec()).unwrap().try_into().unwrap();
+
+        let bytes = HclReportBuilder::new(snp_report)
+            .with_status(1)
+            .build()
+            .unwrap();
+        let result = HclReport::new(bytes.clone());
+        assert!(matches!(result, Err(HclError::FailureStatus(1))));
+        assert_eq!(AttestationHeader::parse(&bytes).unwrap().status(), 1);
+
+        let bytes = HclReportBuilder::new(snp_report)
+            .with_version(3)
+            .build()
+            .unwrap();
+        let result = HclReport::new(bytes);
+        assert!(matches!(result, Err(HclError::UnsupportedVersion(3))));
+
+        let mut bytes = HclReportBuilder::new(snp_report).build().unwrap();
+        bytes[0] = b'X';
+        let result = HclReport::new(bytes);
+        assert!(matches!(result, Err(HclError::InvalidSignature(_))));
+
+        let report_size_offset = offset_of!(AttestationHeader, report_size);
+        let mut bytes = HclReportBuilder::new(snp_report)
+            .with_size(2048)
+            .build()
+            .unwrap();
+        bytes[report_size_offset..report_size_offset + 4].copy_from_slice(&2048u32.to_le_bytes());
+        let result = HclReport::new(bytes);
+        assert!(matches!(result, Err(HclError::ReportSizeMismatch(_, 2048))));
+    }
+
+    #[test]
+    fn reject_malformed_reports() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let var_data_size_offset = offset_of!(AttestationReport, hcl_data)
+            + offset_of!(IgvmRequestData, variable_data_size);
+        let data_size_offset =
+            offset_of!(AttestationReport, hcl_data) + offset_of!(IgvmRequestData, data_size);
+
+        for len in 0..bytes.len() {
+            let _ = HclReport::new(bytes[..len].to_vec());
+        }
+
+        let result = HclReport::new(bytes[..VAR_DATA_OFFSET - 1].to_vec());
+        assert!(matches!(result, Err(HclError::Truncated(_, _))));
+
+        let result = HclReport::new(bytes[..VAR_DATA_OFFSET].to_vec());
+        assert!(matches!(result, Err(HclError::Truncated(_, _))));
+
+        let mut malformed = bytes.to_vec();
+        malformed[data_size_offset..data_size_offset + 4].copy_from_slice(&[0xff; 4]);
+        let result = HclReport::new(malformed);
+        assert!(matches!(result, Err(HclError::InvalidDataSize(_, _))));
+
+        let mut malformed = bytes.to_vec();
+        let var_data_size = (bytes.len() - VAR_DATA_OFFSET + 1) as u32;
+        let data_size = size_of::<IgvmRequestData>() as u32 + var_data_size;
+        malformed[var_data_size_offset..var_data_size_offset + 4]
+            .copy_from_slice(&var_data_size.to_le_bytes());
+        malformed[data_size_offset..data_size_offset + 4].copy_from_slice(&data_size.to_le_bytes());
+        let result = HclReport::new(malformed);
+        assert!(matches!(result, Err(HclError::VarDataOutOfRange)));
+    }
+
+    #[test]
+    fn var_data_hash() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let var_data_hash = hcl_report.var_data_hash().unwrap();
+        assert_eq!(
+            var_data_hash,
+            VarDataHash::Sha256(hcl_report.var_data_sha256())
+        );
+        assert!(hcl_report.var_data_matches_report_data().unwrap());
+
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        assert!(hcl_report.var_data_matches_report_data().unwrap());
+    }
+
+    #[test]
+    fn verify_binding() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        hcl_report.verify_binding().unwrap();
+        hcl_report.verify_binding_zero_padded().unwrap();
+
+        let mut snp_report: SnpReport = hcl_report.try_into().unwrap();
+        let var_data = br#"{"keys":[]}"#.to_vec();
+        let bytes = HclReportBuilder::new(snp_report)
+            .with_var_data(var_data.clone())
+            .build()
+            .unwrap();
+        let hcl_report = HclReport::new(bytes).unwrap();
+        let result = hcl_report.verify_binding();
+        assert!(matches!(result, Err(HclError::BindingMismatch)));
+
+        // a correct hash, followed by non-zero bytes
+        snp_report.report_data = [0xff; 64];
+        snp_report.report_data[..32].copy_from_slice(&Sha256::digest(&var_data));
+        let bytes = HclReportBuilder::new(snp_report)
+            .with_var_data(var_data)
+            .build()
+            .unwrap();
+        let hcl_report = HclReport::new(bytes).unwrap();
+        hcl_report.verify_binding().unwrap();
+        let result = hcl_report.verify_binding_zero_padded();
+        assert!(matches!(result, Err(HclError::NonZeroReportData)));
+    }
+
+    #[test]
+    fn var_data_hash_types() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hash_type_offset = offset_of!(AttestationReport, hcl_data)
+            + offset_of!(IgvmRequestData, report_data_hash_type);
+
+        let mut sha384_bytes = bytes.to_vec();
+        sha384_bytes[hash_type_offset] = IgvmHashType::Sha384 as u8;
+        let hcl_report = HclReport::new(sha384_bytes).unwrap();
+        let var_data_hash = hcl_report.var_data_hash().unwrap();
+        assert!(matches!(var_data_hash, VarDataHash::Sha384(_)));
+        assert!(!hcl_report.var_data_matches_report_data().unwrap());
+
+        let mut sha512_bytes = bytes.to_vec();
+        sha512_bytes[hash_type_offset] = IgvmHashType::Sha512 as u8;
+        let hcl_report = HclReport::new(sha512_bytes).unwrap();
+        assert_eq!(hcl_report.var_data_hash().unwrap().as_bytes().len(), 64);
+
+        let mut invalid_bytes = bytes.to_vec();
+        invalid_bytes[hash_type_offset] = IgvmHashType::Invalid as u8;
+        let hcl_report = HclReport::new(invalid_bytes).unwrap();
+        let error = hcl_report.var_data_hash().unwrap_err();
+        assert!(matches!(
+            error,
+            HclError::UnsupportedHashType(IgvmHashType::Invalid)
+        ));
+    }
+
+    #[test]
+    fn reject_unknown_hash_type() {
+        let bytes: &[u8] = include_bytes!("../../test/hcl-report-snp.bin");
+        let hash_type_offset = offset_of!(AttestationReport, hcl_data)
+            + offset_of!(IgvmRequestData, report_data_hash_type);
+        let mut bytes = bytes.to_vec();
+        bytes[hash_type_offset] = 7;
+        let result = HclReport::new(bytes);
+        assert!(matches!(result, Err(HclError::InvalidHashType(7))));
     }
 }
@@ -2,14 +2,78 @@
 // Licensed under the MIT License.
 
 // Types are based on "Architecture Specification: Intel Trust Domain Extensions
-// Module 1.0", Feb 2023, Section 22.6
+// Module 1.0", Feb 2023, Section 22.6, and the TDX 1.5 extensions in "Intel TDX Module
+// v1.5 ABI Specification", Section 3.4
 
+use bitflags::bitflags;
+use memoffset::offset_of;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
-use zerocopy::AsBytes;
+use std::mem::size_of;
+use thiserror::Error;
+use zerocopy::{AsBytes, FromBytes, FromZeroes, Unaligned};
+
+mod policy;
+pub use policy::{PolicyError, TdPolicy};
+
+const TD_REPORT_SIZE: usize = 1024;
+const REPORT_TYPE_TDX: u8 = 0x81;
+const REPORT_SUBTYPE_TD_REPORT: u8 = 0;
+/// TDX 1.0 reports, `servtd_hash` is not populated
+const REPORT_VERSION_1_0: u8 = 0;
+/// TDX 1.5 reports, which populate `servtd_hash`
+const REPORT_VERSION_1_5: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum TdReportError {
+    #[error("invalid TD report size (expected {0} bytes, found {1})")]
+    SizeMismatch(usize, usize),
+    #[error("unsupported report type {0:#04x}, subtype {1}, version {2}")]
+    UnsupportedReportType(u8, u8, u8),
+}
+
+bitflags! {
+    /// TD attributes, as found in `TdInfo::attributes`
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct TdAttributes: u64 {
+        /// The TD is in debug mode, its state can be read and modified by the host
+        const DEBUG = 1 << 0;
+        const SEPT_VE_DISABLE = 1 << 28;
+        const MIGRATABLE = 1 << 29;
+        const PKS = 1 << 30;
+        const KL = 1 << 31;
+        const PERFMON = 1 << 63;
+    }
+}
+
+bitflags! {
+    /// Extended features available to the TD, in the layout of XCR0 and IA32_XSS
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct Xfam: u64 {
+        const X87 = 1 << 0;
+        const SSE = 1 << 1;
+        const AVX = 1 << 2;
+        const AVX512_OPMASK = 1 << 5;
+        const AVX512_ZMM_HI256 = 1 << 6;
+        const AVX512_HI16_ZMM = 1 << 7;
+        const PT = 1 << 8;
+        const PKRU = 1 << 9;
+        const PASID = 1 << 10;
+        const CET_U = 1 << 11;
+        const CET_S = 1 << 12;
+        const HDC = 1 << 13;
+        const UINTR = 1 << 14;
+        const LBR = 1 << 15;
+        const HWP = 1 << 16;
+        const AMX_TILECFG = 1 << 17;
+        const AMX_TILEDATA = 1 << 18;
+    }
+}
 
 #[repr(C)]
-#[derive(AsBytes, Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(
+    AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, Serialize, Deserialize, PartialEq,
+)]
 pub struct ReportType {
     pub r#type: u8,
     pub subtype: u8,
@@ -18,7 +82,9 @@ pub struct ReportType {
 }
 
 #[repr(C)]
-#[derive(AsBytes, Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(
+    AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, Serialize, Deserialize, PartialEq,
+)]
 pub struct ReportMac {
     pub reporttype: ReportType,
     pub _reserved_1: [u8; 12],
@@ -34,14 +100,36 @@ pub struct ReportMac {
 }
 
 #[repr(C)]
-#[derive(AsBytes, Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(
+    AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, Serialize, Deserialize, PartialEq,
+)]
 pub struct Rtmr {
     #[serde(with = "BigArray")]
     pub register_data: [u8; 48],
 }
 
 #[repr(C)]
-#[derive(AsBytes, Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(
+    AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, Serialize, Deserialize, PartialEq,
+)]
+pub struct TeeTcbInfo {
+    pub valid: [u8; 8],
+    pub tee_tcb_svn: [u8; 16],
+    #[serde(with = "BigArray")]
+    pub mrseam: [u8; 48],
+    #[serde(with = "BigArray")]
+    pub mrsignerseam: [u8; 48],
+    pub attributes: [u8; 8],
+    /// SVNs of the TDX module, since TDX 1.5
+    pub tee_tcb_svn2: [u8; 16],
+    #[serde(with = "BigArray")]
+    pub _reserved: [u8; 95],
+}
+
+#[repr(C)]
+#[derive(
+    AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, Serialize, Deserialize, PartialEq,
+)]
 pub struct TdInfo {
     pub attributes: [u8; 8],
     pub xfam: [u8; 8],
@@ -54,16 +142,176 @@ pub struct TdInfo {
     #[serde(with = "BigArray")]
     pub mrownerconfig: [u8; 48],
     pub rtrm: [Rtmr; 4],
+    /// Hash of the service TDs bound to the TD, zeroed before TDX 1.5
     #[serde(with = "BigArray")]
-    pub _reserved: [u8; 112],
+    pub servtd_hash: [u8; 48],
+    #[serde(with = "BigArray")]
+    pub _reserved: [u8; 64],
 }
 
 #[repr(C)]
-#[derive(AsBytes, Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(
+    AsBytes, FromBytes, FromZeroes, Unaligned, Copy, Clone, Debug, Serialize, Deserialize, PartialEq,
+)]
 pub struct TdReport {
     pub report_mac: ReportMac,
-    #[serde(with = "BigArray")]
-    pub tee_tcb_info: [u8; 239],
+    pub tee_tcb_info: TeeTcbInfo,
     pub _reserved: [u8; 17],
     pub tdinfo: TdInfo,
 }
+
+impl TdReport {
+    /// Parse a TD report, the input must be exactly 1024 bytes. Report types other than TDX
+    /// 1.0 and 1.5 TD reports are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TdReportError> {
+        let td_report = Self::read_from(bytes)
+            .ok_or(TdReportError::SizeMismatch(TD_REPORT_SIZE, bytes.len()))?;
+        let ReportType {
+            r#type,
+            subtype,
+            version,
+            ..
+        } = td_report.report_mac.reporttype;
+        if r#type != REPORT_TYPE_TDX
+            || subtype != REPORT_SUBTYPE_TD_REPORT
+            || !matches!(version, REPORT_VERSION_1_0 | REPORT_VERSION_1_5)
+        {
+            return Err(TdReportError::UnsupportedReportType(
+                r#type, subtype, version,
+            ));
+        }
+        Ok(td_report)
+    }
+
+    /// The hash of the service TDs bound to the TD, if the report's version populates it
+    pub fn servtd_hash(&self) -> Option<&[u8; 48]> {
+        match self.report_mac.reporttype.version {
+            REPORT_VERSION_1_0 => None,
+            _ => Some(&self.tdinfo.servtd_hash),
+        }
+    }
+}
+
+impl TdInfo {
+    /// Decode the TD attributes, unknown bits are retained
+    pub fn td_attributes(&self) -> TdAttributes {
+        TdAttributes::from_bits_retain(u64::from_le_bytes(self.attributes))
+    }
+
+    /// Decode the XFAM, unknown bits are retained
+    pub fn xfam_features(&self) -> Xfam {
+        Xfam::from_bits_retain(u64::from_le_bytes(self.xfam))
+    }
+}
+
+const _: () = assert!(size_of::<ReportMac>() == 256);
+const _: () = assert!(offset_of!(ReportMac, cpusvn) == 16);
+const _: () = assert!(offset_of!(ReportMac, tee_tcb_info_hash) == 32);
+const _: () = assert!(offset_of!(ReportMac, tee_info_hash) == 80);
+const _: () = assert!(offset_of!(ReportMac, reportdata) == 128);
+const _: () = assert!(offset_of!(ReportMac, mac) == 224);
+const _: () = assert!(offset_of!(TeeTcbInfo, tee_tcb_svn2) == 128);
+const _: () = assert!(size_of::<TeeTcbInfo>() == 239);
+const _: () = assert!(size_of::<TdInfo>() == 512);
+const _: () = assert!(offset_of!(TdInfo, mrtd) == 16);
+const _: () = assert!(offset_of!(TdInfo, mrconfigid) == 64);
+const _: () = assert!(offset_of!(TdInfo, mrowner) == 112);
+const _: () = assert!(offset_of!(TdInfo, mrownerconfig) == 160);
+const _: () = assert!(offset_of!(TdInfo, rtrm) == 208);
+const _: () = assert!(offset_of!(TdInfo, servtd_hash) == 400);
+const _: () = assert!(offset_of!(TdInfo, _reserved) == 448);
+const _: () = assert!(size_of::<TdReport>() == TD_REPORT_SIZE);
+const _: () = assert!(offset_of!(TdReport, tee_tcb_info) == 256);
+const _: () = assert!(offset_of!(TdReport, tdinfo) == 512);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hcl::HclReport;
+
+    #[test]
+    fn decode_tee_tcb_info() {
+        let bytes = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let td_report: TdReport = hcl_report.try_into().unwrap();
+        let tee_tcb_info = td_report.tee_tcb_info;
+        assert_eq!(tee_tcb_info.valid, [0xff, 0x01, 0x03, 0, 0, 0, 0, 0]);
+        assert_eq!(tee_tcb_info.tee_tcb_svn[..3], [0x02, 0x01, 0x06]);
+        assert_eq!(tee_tcb_info.mrseam[..4], [0x36, 0x03, 0x04, 0xd3]);
+        assert_eq!(tee_tcb_info.mrsignerseam, [0; 48]);
+        assert_eq!(tee_tcb_info.attributes, [0; 8]);
+        assert_eq!(tee_tcb_info.tee_tcb_svn2[..3], [0x02, 0x01, 0x06]);
+    }
+
+    #[test]
+    fn parse_td_report() {
+        let bytes = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let td_report = TdReport::from_bytes(hcl_report.hw_report()).unwrap();
+        assert_eq!(td_report.as_bytes(), hcl_report.hw_report());
+
+        let bytes = td_report.as_bytes();
+        assert!(matches!(
+            TdReport::from_bytes(&bytes[..1023]),
+            Err(TdReportError::SizeMismatch(1024, 1023))
+        ));
+        assert!(matches!(
+            TdReport::from_bytes(&[bytes, &[0]].concat()),
+            Err(TdReportError::SizeMismatch(1024, 1025))
+        ));
+    }
+
+    #[test]
+    fn check_report_type() {
+        let bytes = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let mut td_report = TdReport::from_bytes(hcl_report.hw_report()).unwrap();
+        assert_eq!(td_report.report_mac.reporttype.version, 0);
+        assert_eq!(td_report.servtd_hash(), None);
+
+        td_report.report_mac.reporttype.version = 1;
+        td_report.tdinfo.servtd_hash = [0xab; 48];
+        let td_report = TdReport::from_bytes(td_report.as_bytes()).unwrap();
+        assert_eq!(td_report.servtd_hash(), Some(&[0xab; 48]));
+
+        for (r#type, subtype, version) in [(0x00, 0, 0), (0x81, 1, 0), (0x81, 0, 2)] {
+            let mut report = td_report;
+            report.report_mac.reporttype.r#type = r#type;
+            report.report_mac.reporttype.subtype = subtype;
+            report.report_mac.reporttype.version = version;
+            assert!(matches!(
+                TdReport::from_bytes(report.as_bytes()),
+                Err(TdReportError::UnsupportedReportType(t, s, v))
+                    if (t, s, v) == (r#type, subtype, version)
+            ));
+        }
+    }
+
+    #[test]
+    fn decode_td_attributes_and_xfam() {
+        let bytes = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let mut td_report: TdReport = hcl_report.try_into().unwrap();
+        assert!(td_report.tdinfo.td_attributes().is_empty());
+        assert_eq!(
+            td_report.tdinfo.xfam_features(),
+            Xfam::X87
+                | Xfam::SSE
+                | Xfam::AVX
+                | Xfam::AVX512_OPMASK
+                | Xfam::AVX512_ZMM_HI256
+                | Xfam::AVX512_HI16_ZMM
+                | Xfam::CET_U
+                | Xfam::CET_S
+                | Xfam::AMX_TILECFG
+                | Xfam::AMX_TILEDATA
+        );
+
+        td_report.tdinfo.attributes = (TdAttributes::DEBUG | TdAttributes::PERFMON)
+            .bits()
+            .to_le_bytes();
+        let attributes = td_report.tdinfo.td_attributes();
+        assert!(attributes.contains(TdAttributes::DEBUG));
+        assert!(attributes.contains(TdAttributes::PERFMON));
+    }
+}
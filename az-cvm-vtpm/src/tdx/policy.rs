@@ -0,0 +1,96 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use super::{TdAttributes, TdReport, Xfam};
+use thiserror::Error;
+
+/// XFAM features that are expected in a production TD
+const DEFAULT_ALLOWED_XFAM: Xfam = Xfam::X87
+    .union(Xfam::SSE)
+    .union(Xfam::AVX)
+    .union(Xfam::AVX512_OPMASK)
+    .union(Xfam::AVX512_ZMM_HI256)
+    .union(Xfam::AVX512_HI16_ZMM)
+    .union(Xfam::PKRU)
+    .union(Xfam::CET_U)
+    .union(Xfam::CET_S)
+    .union(Xfam::AMX_TILECFG)
+    .union(Xfam::AMX_TILEDATA);
+
+#[derive(Error, Debug)]
+pub enum PolicyError {
+    #[error("TD is in debug mode")]
+    DebugTd,
+    #[error("unexpected XFAM features {0:?}")]
+    UnexpectedXfam(Xfam),
+}
+
+/// Policy for the attributes and XFAM of a TD. The default policy rejects debug TDs and XFAM
+/// features outside of the common x87/SSE/AVX/AVX-512/PKRU/CET/AMX set, e.g. PT or LBR.
+#[derive(Clone, Debug)]
+pub struct TdPolicy {
+    pub allow_debug: bool,
+    pub allowed_xfam: Xfam,
+}
+
+impl Default for TdPolicy {
+    fn default() -> Self {
+        Self {
+            allow_debug: false,
+            allowed_xfam: DEFAULT_ALLOWED_XFAM,
+        }
+    }
+}
+
+impl TdPolicy {
+    /// Check TD attributes and XFAM against the policy
+    pub fn check(&self, attributes: TdAttributes, xfam: Xfam) -> Result<(), PolicyError> {
+        if !self.allow_debug && attributes.contains(TdAttributes::DEBUG) {
+            return Err(PolicyError::DebugTd);
+        }
+        let unexpected = xfam.difference(self.allowed_xfam);
+        if !unexpected.is_empty() {
+            return Err(PolicyError::UnexpectedXfam(unexpected));
+        }
+        Ok(())
+    }
+
+    /// Check the TD attributes and XFAM of a TdReport against the policy
+    pub fn check_td_report(&self, td_report: &TdReport) -> Result<(), PolicyError> {
+        let tdinfo = &td_report.tdinfo;
+        self.check(tdinfo.td_attributes(), tdinfo.xfam_features())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hcl::HclReport;
+
+    #[test]
+    fn check_policy() {
+        let bytes = include_bytes!("../../test/hcl-report-tdx.bin");
+        let hcl_report = HclReport::new(bytes.to_vec()).unwrap();
+        let td_report: TdReport = hcl_report.try_into().unwrap();
+        let policy = TdPolicy::default();
+        policy.check_td_report(&td_report).unwrap();
+
+        let xfam = td_report.tdinfo.xfam_features();
+        assert!(matches!(
+            policy.check(TdAttributes::DEBUG, xfam),
+            Err(PolicyError::DebugTd)
+        ));
+        assert!(matches!(
+            policy.check(TdAttributes::empty(), xfam | Xfam::PT),
+            Err(PolicyError::UnexpectedXfam(Xfam::PT))
+        ));
+
+        let policy = TdPolicy {
+            allow_debug: true,
+            allowed_xfam: Xfam::all(),
+        };
+        policy
+            .check(TdAttributes::DEBUG, xfam | Xfam::PT | Xfam::LBR)
+            .unwrap();
+    }
+}
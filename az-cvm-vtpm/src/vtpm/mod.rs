@@ -1,8 +1,11 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use crate::hcl::{HclError, HclReport};
 use rsa::{BigUint, RsaPublicKey};
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeTuple;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 use thiserror::Error;
 use tss_esapi::abstraction::nv;
 use tss_esapi::abstraction::pcr;
@@ -13,42 +16,21 @@ use tss_esapi::interface_types::resource_handles::NvAuth;
 use tss_esapi::interface_types::session_handles::AuthSession;
 use tss_esapi::structures::pcr_selection_list::PcrSelectionListBuilder;
 use tss_esapi::structures::pcr_slot::PcrSlot;
-use tss_esapi::structures::{Attest, AttestInfo, Data, Signature, SignatureScheme};
-use tss_esapi::tcti_ldr::{DeviceConfig, TctiNameConf};
+use tss_esapi::structures::{
+    Attest, AttestInfo, Data, PcrSelectionList, Signature, SignatureScheme,
+};
+use tss_esapi::tcti_ldr::DeviceConfig;
 use tss_esapi::traits::{Marshall, UnMarshall};
 use tss_esapi::Context;
 
+pub use tss_esapi::tcti_ldr::TctiNameConf;
+
 #[cfg(feature = "verifier")]
 mod verify;
 
 const VTPM_HCL_REPORT_NV_INDEX: u32 = 0x01400001;
 const VTPM_AK_HANDLE: u32 = 0x81000003;
-const VTPM_QUOTE_PCR_SLOTS: [PcrSlot; 24] = [
-    PcrSlot::Slot0,
-    PcrSlot::Slot1,
-    PcrSlot::Slot2,
-    PcrSlot::Slot3,
-    PcrSlot::Slot4,
-    PcrSlot::Slot5,
-    PcrSlot::Slot6,
-    PcrSlot::Slot7,
-    PcrSlot::Slot8,
-    PcrSlot::Slot9,
-    PcrSlot::Slot10,
-    PcrSlot::Slot11,
-    PcrSlot::Slot12,
-    PcrSlot::Slot13,
-    PcrSlot::Slot14,
-    PcrSlot::Slot15,
-    PcrSlot::Slot16,
-    PcrSlot::Slot17,
-    PcrSlot::Slot18,
-    PcrSlot::Slot19,
-    PcrSlot::Slot20,
-    PcrSlot::Slot21,
-    PcrSlot::Slot22,
-    PcrSlot::Slot23,
-];
+const PCR_SLOT_COUNT: u8 = 24;
 
 #[derive(Error, Debug)]
 pub enum ReportError {
@@ -58,16 +40,7 @@ pub enum ReportError {
 
 /// Get a HCL report from an nvindex
 pub fn get_report() -> Result<Vec<u8>, ReportError> {
-    use tss_esapi::handles::NvIndexTpmHandle;
-    let nv_index = NvIndexTpmHandle::new(VTPM_HCL_REPORT_NV_INDEX)?;
-
-    let conf: TctiNameConf = TctiNameConf::Device(DeviceConfig::default());
-    let mut context = Context::new(conf)?;
-    let auth_session = AuthSession::Password;
-    context.set_sessions((Some(auth_session), None, None));
-
-    let report = nv::read_full(&mut context, NvAuth::Owner, nv_index)?;
-    Ok(report)
+    VtpmSession::open_device()?.get_report()
 }
 
 #[derive(Error, Debug)]
@@ -78,28 +51,21 @@ pub enum AKPubError {
     WrongKeyType,
     #[error("rsa error")]
     OpenSsl(#[from] rsa::errors::Error),
+    #[error("HCL error")]
+    Hcl(#[from] HclError),
+    #[error("AKpub in HCL report does not match the vTPM's AK")]
+    AkPubMismatch,
 }
 
 /// Get the AK pub of the vTPM
 pub fn get_ak_pub() -> Result<RsaPublicKey, AKPubError> {
-    let conf: TctiNameConf = TctiNameConf::Device(DeviceConfig::default());
-    let mut context = Context::new(conf)?;
-    let tpm_handle: TpmHandle = VTPM_AK_HANDLE.try_into()?;
-    let key_handle = context.tr_from_tpm_public(tpm_handle)?;
-    let (pk, _, _) = context.read_public(key_handle.into())?;
-
-    let decoded_key: DecodedKey = pk.try_into()?;
-    let DecodedKey::RsaPublicKey(rsa_pk) = decoded_key else {
-        return Err(AKPubError::WrongKeyType);
-    };
-
-    let bytes = rsa_pk.modulus.as_unsigned_bytes_be();
-    let n = BigUint::from_bytes_be(bytes);
-    let bytes = rsa_pk.public_exponent.as_unsigned_bytes_be();
-    let e = BigUint::from_bytes_be(bytes);
+    VtpmSession::open_device()?.get_ak_pub()
+}
 
-    let pkey = RsaPublicKey::new(n, e)?;
-    Ok(pkey)
+/// Verify that the AKpub in a HCL report matches the AK of the vTPM, so a mismatch can be
+/// detected before evidence is sent to a verifier
+pub fn verify_ak_pub(hcl_report: &HclReport) -> Result<(), AKPubError> {
+    VtpmSession::open_device()?.verify_ak_pub(hcl_report)
 }
 
 #[non_exhaustive]
@@ -117,16 +83,396 @@ pub enum QuoteError {
     PcrBankNotFound,
     #[error("PCR reading error")]
     PcrRead,
+    #[error("invalid PCR slot {0}")]
+    InvalidPcrSlot(u8),
+    #[error("unsupported PCR bank")]
+    UnsupportedPcrBank,
+    #[error("quote does not cover exactly one PCR bank")]
+    InvalidPcrSelection,
+    #[error("expected {0} PCR values, found {1}")]
+    PcrCountMismatch(usize, usize),
+    #[error("quote serialization error")]
+    Serialization(#[from] bincode::Error),
+}
+
+/// The hash algorithm of a PCR bank
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PcrBank {
+    Sha1,
+    Sha256,
+    Sha384,
+}
+
+impl PcrBank {
+    /// The size of a PCR value in this bank
+    pub fn digest_size(&self) -> usize {
+        match self {
+            PcrBank::Sha1 => 20,
+            PcrBank::Sha256 => 32,
+            PcrBank::Sha384 => 48,
+        }
+    }
+}
+
+impl From<PcrBank> for HashingAlgorithm {
+    fn from(bank: PcrBank) -> Self {
+        match bank {
+            PcrBank::Sha1 => HashingAlgorithm::Sha1,
+            PcrBank::Sha256 => HashingAlgorithm::Sha256,
+            PcrBank::Sha384 => HashingAlgorithm::Sha384,
+        }
+    }
+}
+
+impl TryFrom<HashingAlgorithm> for PcrBank {
+    type Error = QuoteError;
+
+    fn try_from(hash_algo: HashingAlgorithm) -> Result<Self, Self::Error> {
+        match hash_algo {
+            HashingAlgorithm::Sha1 => Ok(PcrBank::Sha1),
+            HashingAlgorithm::Sha256 => Ok(PcrBank::Sha256),
+            HashingAlgorithm::Sha384 => Ok(PcrBank::Sha384),
+            _ => Err(QuoteError::UnsupportedPcrBank),
+        }
+    }
+}
+
+/// The PCR slots of a bank that are covered by a quote
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcrSelection {
+    bank: PcrBank,
+    slots: Vec<u8>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl Default for PcrSelection {
+    /// All 24 PCR slots of the SHA-256 bank
+    fn default() -> Self {
+        Self {
+            bank: PcrBank::Sha256,
+            slots: (0..PCR_SLOT_COUNT).collect(),
+        }
+    }
+}
+
+impl PcrSelection {
+    /// Select PCR slots of a bank, the slots are sorted in the order the TPM reports them
+    ///
+    /// # Arguments
+    ///
+    /// * `bank` - The hash algorithm of the PCR bank
+    ///
+    /// * `slots` - The PCR slot numbers, 0 to 23
+    pub fn new(bank: PcrBank, slots: &[u8]) -> Result<Self, QuoteError> {
+        if let Some(&slot) = slots.iter().find(|&&slot| slot >= PCR_SLOT_COUNT) {
+            return Err(QuoteError::InvalidPcrSlot(slot));
+        }
+        let mut slots = slots.to_vec();
+        slots.sort_unstable();
+        slots.dedup();
+        Ok(Self { bank, slots })
+    }
+
+    pub fn bank(&self) -> PcrBank {
+        self.bank
+    }
+
+    pub fn slots(&self) -> &[u8] {
+        &self.slots
+    }
+
+    fn to_selection_list(&self) -> Result<PcrSelectionList, QuoteError> {
+        let pcr_slots = self
+            .slots
+            .iter()
+            .map(|&slot| PcrSlot::try_from(1u32 << slot))
+            .collect::<Result<Vec<_>, _>>()?;
+        let selection_list = PcrSelectionListBuilder::new()
+            .with_selection(self.bank.into(), &pcr_slots)
+            .build()?;
+        Ok(selection_list)
+    }
+
+    fn from_selection_list(selection_list: &PcrSelectionList) -> Result<Self, QuoteError> {
+        let [selection] = selection_list.get_selections() else {
+            return Err(QuoteError::InvalidPcrSelection);
+        };
+        let bank = selection.hashing_algorithm().try_into()?;
+        let slots = selection.selected().into_iter().map(slot_number).collect();
+        Ok(Self { bank, slots })
+    }
+}
+
+fn slot_number(pcr_slot: PcrSlot) -> u8 {
+    u32::from(pcr_slot).trailing_zeros() as u8
+}
+
+/// The signature scheme of a Quote
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuoteSignatureScheme {
+    RsaSsaSha256,
+}
+
+/// The value of a PCR slot
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcrValue {
+    pub slot: u8,
+    pub value: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Quote {
+    signature_scheme: QuoteSignatureScheme,
+    signature: Vec<u8>,
+    message: Vec<u8>,
+    pcr_bank: PcrBank,
+    pcrs: Vec<PcrValue>,
+}
+
+/// The current version of the Quote serialization format
+const QUOTE_FORMAT_VERSION: u32 = 1;
+
+/// The fields of a Quote in version 1 of the serialization format
+#[derive(Serialize, Deserialize, Clone)]
+struct QuoteV1 {
+    signature_scheme: QuoteSignatureScheme,
+    signature: Vec<u8>,
+    message: Vec<u8>,
+    pcr_bank: PcrBank,
+    pcrs: Vec<PcrValue>,
+}
+
+/// The versioned format in human-readable encodings, e.g. JSON
+#[derive(Serialize, Deserialize)]
+struct VersionedQuote {
+    version: u32,
+    #[serde(flatten)]
+    quote: QuoteV1,
+}
+
+/// The unversioned format, which assumes that the PCR values are in the order of the quote's
+/// PCR selection
+#[derive(Serialize, Deserialize)]
+struct LegacyQuote {
     signature: Vec<u8>,
     message: Vec<u8>,
     pcrs: Vec<Vec<u8>>,
 }
 
+/// A deserialized Quote in either format
+enum QuoteRepr {
+    V1(QuoteV1),
+    Legacy(LegacyQuote),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HumanReadableQuote {
+    Versioned(VersionedQuote),
+    Legacy(LegacyQuote),
+}
+
+/// Binary encodings like bincode are not self-describing, so the versioned format is a tuple
+/// that starts with an empty byte sequence, followed by the format version and the fields. In
+/// the unversioned format the same position holds the signature, which is never empty.
+struct BinaryQuoteVisitor;
+
+impl<'de> de::Visitor<'de> for BinaryQuoteVisitor {
+    type Value = QuoteRepr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a versioned or legacy quote")
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<QuoteRepr, A::Error> {
+        let head: Vec<u8> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        if !head.is_empty() {
+            let message = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            let pcrs = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+            return Ok(QuoteRepr::Legacy(LegacyQuote {
+                signature: head,
+                message,
+                pcrs,
+            }));
+        }
+        let version: u32 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        if version != QUOTE_FORMAT_VERSION {
+            return Err(de::Error::custom(format!(
+                "unsupported quote format version {version}"
+            )));
+        }
+        let quote = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok(QuoteRepr::V1(quote))
+    }
+}
+
+impl<'de> Deserialize<'de> for QuoteRepr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return deserializer.deserialize_tuple(3, BinaryQuoteVisitor);
+        }
+        match HumanReadableQuote::deserialize(deserializer)? {
+            HumanReadableQuote::Versioned(VersionedQuote { version, quote }) => {
+                if version != QUOTE_FORMAT_VERSION {
+                    return Err(de::Error::custom(format!(
+                        "unsupported quote format version {version}"
+                    )));
+                }
+                Ok(QuoteRepr::V1(quote))
+            }
+            HumanReadableQuote::Legacy(legacy_quote) => Ok(QuoteRepr::Legacy(legacy_quote)),
+        }
+    }
+}
+
+impl Serialize for Quote {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let quote = QuoteV1::from(self.clone());
+        if serializer.is_human_readable() {
+            let versioned_quote = VersionedQuote {
+                version: QUOTE_FORMAT_VERSION,
+                quote,
+            };
+            return versioned_quote.serialize(serializer);
+        }
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(&Vec::<u8>::new())?;
+        tuple.serialize_element(&QUOTE_FORMAT_VERSION)?;
+        tuple.serialize_element(&quote)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Quote {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        QuoteRepr::deserialize(deserializer)?
+            .try_into()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl From<Quote> for QuoteV1 {
+    fn from(quote: Quote) -> Self {
+        let Quote {
+            signature_scheme,
+            signature,
+            message,
+            pcr_bank,
+            pcrs,
+        } = quote;
+        QuoteV1 {
+            signature_scheme,
+            signature,
+            message,
+            pcr_bank,
+            pcrs,
+        }
+    }
+}
+
+impl From<QuoteV1> for Quote {
+    fn from(quote: QuoteV1) -> Self {
+        let QuoteV1 {
+            signature_scheme,
+            signature,
+            message,
+            pcr_bank,
+            pcrs,
+        } = quote;
+        Quote {
+            signature_scheme,
+            signature,
+            message,
+            pcr_bank,
+            pcrs,
+        }
+    }
+}
+
+impl TryFrom<QuoteRepr> for Quote {
+    type Error = QuoteError;
+
+    fn try_from(quote_repr: QuoteRepr) -> Result<Self, Self::Error> {
+        match quote_repr {
+            QuoteRepr::V1(quote) => Ok(quote.into()),
+            QuoteRepr::Legacy(legacy_quote) => legacy_quote.try_into(),
+        }
+    }
+}
+
+impl TryFrom<LegacyQuote> for Quote {
+    type Error = QuoteError;
+
+    fn try_from(legacy_quote: LegacyQuote) -> Result<Self, Self::Error> {
+        let LegacyQuote {
+            signature,
+            message,
+            pcrs,
+        } = legacy_quote;
+        let mut quote = Quote {
+            signature_scheme: QuoteSignatureScheme::RsaSsaSha256,
+            signature,
+            message,
+            pcr_bank: PcrBank::Sha256,
+            pcrs: vec![],
+        };
+        let pcr_selection = quote.pcr_selection()?;
+        if pcr_selection.slots().len() != pcrs.len() {
+            return Err(QuoteError::PcrCountMismatch(
+                pcr_selection.slots().len(),
+                pcrs.len(),
+            ));
+        }
+        quote.pcr_bank = pcr_selection.bank();
+        quote.pcrs = pcr_selection
+            .slots()
+            .iter()
+            .zip(pcrs)
+            .map(|(&slot, value)| PcrValue { slot, value })
+            .collect();
+        Ok(quote)
+    }
+}
+
 impl Quote {
+    /// Serialize a Quote into the versioned binary format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, QuoteError> {
+        let bytes = bincode::serialize(self)?;
+        Ok(bytes)
+    }
+
+    /// Deserialize a Quote from the versioned binary format, or from the unversioned format of
+    /// earlier releases
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, QuoteError> {
+        let quote_repr: QuoteRepr = bincode::deserialize(bytes)?;
+        quote_repr.try_into()
+    }
+
+    /// The signature scheme of the Quote
+    pub fn signature_scheme(&self) -> QuoteSignatureScheme {
+        self.signature_scheme
+    }
+
+    /// The PCR bank of the bundled PCR values
+    pub fn pcr_bank(&self) -> PcrBank {
+        self.pcr_bank
+    }
+
+    /// The bundled PCR values with their slot numbers
+    pub fn pcr_values(&self) -> &[PcrValue] {
+        &self.pcrs
+    }
+
     /// Extract nonce from a Quote
     pub fn nonce(&self) -> Result<Vec<u8>, QuoteError> {
         let attest = Attest::unmarshall(&self.message)?;
@@ -138,64 +484,287 @@ impl Quote {
     pub fn message(&self) -> Vec<u8> {
         self.message.clone()
     }
+
+    /// Extract the PCR bank and slots that are covered by a Quote
+    pub fn pcr_selection(&self) -> Result<PcrSelection, QuoteError> {
+        let attest = Attest::unmarshall(&self.message)?;
+        let AttestInfo::Quote { info } = attest.attested() else {
+            return Err(QuoteError::NotAQuote);
+        };
+        PcrSelection::from_selection_list(info.pcr_selection())
+    }
 }
 
-/// Get a signed vTPM Quote
+/// A session with the vTPM, which keeps a single TPM context open for multiple operations
+pub struct VtpmSession {
+    context: Context,
+}
+
+impl VtpmSession {
+    /// Open a session with a TPM, e.g. the vTPM device, swtpm, mssim or tpm2-abrmd
+    ///
+    /// # Arguments
+    ///
+    /// * `conf` - The TCTI to connect to the TPM
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use az_cvm_vtpm::vtpm::{TctiNameConf, VtpmSession};
+    /// use std::str::FromStr;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let conf = TctiNameConf::from_str("swtpm:host=localhost,port=2321")?;
+    /// let mut vtpm_session = VtpmSession::new(conf)?;
+    /// let ak_pub = vtpm_session.get_ak_pub()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(conf: TctiNameConf) -> Result<Self, tss_esapi::Error> {
+        let context = Context::new(conf)?;
+        Ok(Self { context })
+    }
+
+    /// Open a session with the vTPM device of the CVM
+    pub fn open_device() -> Result<Self, tss_esapi::Error> {
+        Self::new(TctiNameConf::Device(DeviceConfig::default()))
+    }
+
+    /// Get a HCL report from an nvindex
+    pub fn get_report(&mut self) -> Result<Vec<u8>, ReportError> {
+        use tss_esapi::handles::NvIndexTpmHandle;
+        let nv_index = NvIndexTpmHandle::new(VTPM_HCL_REPORT_NV_INDEX)?;
+
+        let auth_session = AuthSession::Password;
+        self.context.set_sessions((Some(auth_session), None, None));
+        let report = nv::read_full(&mut self.context, NvAuth::Owner, nv_index);
+        self.context.clear_sessions();
+
+        Ok(report?)
+    }
+
+    /// Get the AK pub of the vTPM
+    pub fn get_ak_pub(&mut self) -> Result<RsaPublicKey, AKPubError> {
+        let tpm_handle: TpmHandle = VTPM_AK_HANDLE.try_into()?;
+        let key_handle = self.context.tr_from_tpm_public(tpm_handle)?;
+        let (pk, _, _) = self.context.read_public(key_handle.into())?;
+
+        let decoded_key: DecodedKey = pk.try_into()?;
+        let DecodedKey::RsaPublicKey(rsa_pk) = decoded_key else {
+            return Err(AKPubError::WrongKeyType);
+        };
+
+        let bytes = rsa_pk.modulus.as_unsigned_bytes_be();
+        let n = BigUint::from_bytes_be(bytes);
+        let bytes = rsa_pk.public_exponent.as_unsigned_bytes_be();
+        let e = BigUint::from_bytes_be(bytes);
+
+        let pkey = RsaPublicKey::new(n, e)?;
+        Ok(pkey)
+    }
+
+    /// Verify that the AKpub in a HCL report matches the AK of the vTPM
+    pub fn verify_ak_pub(&mut self, hcl_report: &HclReport) -> Result<(), AKPubError> {
+        let ak_pub = self.get_ak_pub()?;
+        if !hcl_report.ak_pub_matches(&ak_pub)? {
+            return Err(AKPubError::AkPubMismatch);
+        }
+        Ok(())
+    }
+
+    /// Get a signed vTPM Quote over all PCRs of the SHA-256 bank
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A byte slice to use as nonce
+    pub fn get_quote(&mut self, data: &[u8]) -> Result<Quote, QuoteError> {
+        self.get_quote_with_selection(data, &PcrSelection::default())
+    }
+
+    /// Get a signed vTPM Quote over the selected PCRs
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A byte slice to use as nonce
+    ///
+    /// * `pcr_selection` - The PCR bank and slots to quote
+    pub fn get_quote_with_selection(
+        &mut self,
+        data: &[u8],
+        pcr_selection: &PcrSelection,
+    ) -> Result<Quote, QuoteError> {
+        if data.len() > Data::MAX_SIZE {
+            return Err(QuoteError::DataTooLarge);
+        }
+        let context = &mut self.context;
+        let tpm_handle: TpmHandle = VTPM_AK_HANDLE.try_into()?;
+        let key_handle = context.tr_from_tpm_public(tpm_handle)?;
+
+        let quote_data: Data = data.try_into()?;
+        let scheme = SignatureScheme::Null;
+        let hash_algo = pcr_selection.bank().into();
+        let selection_list = pcr_selection.to_selection_list()?;
+
+        let auth_session = AuthSession::Password;
+        context.set_sessions((Some(auth_session), None, None));
+
+        let quote = context.quote(
+            key_handle.into(),
+            quote_data,
+            scheme,
+            selection_list.clone(),
+        );
+        context.clear_sessions();
+        let (attest, signature) = quote?;
+
+        let AttestInfo::Quote { .. } = attest.attested() else {
+            return Err(QuoteError::NotAQuote);
+        };
+        let Signature::RsaSsa(rsa_sig) = signature else {
+            return Err(QuoteError::WrongSignature);
+        };
+        if rsa_sig.hashing_algorithm() != HashingAlgorithm::Sha256 {
+            return Err(QuoteError::WrongSignature);
+        }
+
+        let signature = rsa_sig.signature().to_vec();
+        let message = attest.marshall()?;
+
+        let pcr_data = pcr::read_all(context, selection_list)?;
+
+        let pcr_bank = pcr_data
+            .pcr_bank(hash_algo)
+            .ok_or(QuoteError::PcrBankNotFound)?;
+
+        let pcrs = pcr_bank
+            .into_iter()
+            .map(|(&slot, x)| PcrValue {
+                slot: slot_number(slot),
+                value: x.value().to_vec(),
+            })
+            .collect();
+
+        Ok(Quote {
+            signature_scheme: QuoteSignatureScheme::RsaSsaSha256,
+            signature,
+            message,
+            pcr_bank: pcr_selection.bank(),
+            pcrs,
+        })
+    }
+}
+
+/// Get a signed vTPM Quote over all PCRs of the SHA-256 bank
 ///
 /// # Arguments
 ///
 /// * `data` - A byte slice to use as nonce
 pub fn get_quote(data: &[u8]) -> Result<Quote, QuoteError> {
-    if data.len() > Data::MAX_SIZE {
-        return Err(QuoteError::DataTooLarge);
-    }
-    let conf: TctiNameConf = TctiNameConf::Device(DeviceConfig::default());
-    let mut context = Context::new(conf)?;
-    let tpm_handle: TpmHandle = VTPM_AK_HANDLE.try_into()?;
-    let key_handle = context.tr_from_tpm_public(tpm_handle)?;
-
-    let quote_data: Data = data.try_into()?;
-    let scheme = SignatureScheme::Null;
-    let hash_algo = HashingAlgorithm::Sha256;
-    let selection_list = PcrSelectionListBuilder::new()
-        .with_selection(hash_algo, &VTPM_QUOTE_PCR_SLOTS)
-        .build()?;
-
-    let auth_session = AuthSession::Password;
-    context.set_sessions((Some(auth_session), None, None));
-
-    let (attest, signature) = context.quote(
-        key_handle.into(),
-        quote_data,
-        scheme,
-        selection_list.clone(),
-    )?;
-
-    let AttestInfo::Quote { .. } = attest.attested() else {
-        return Err(QuoteError::NotAQuote);
-    };
-    let Signature::RsaSsa(rsa_sig) = signature else {
-        return Err(QuoteError::WrongSignature);
-    };
-
-    let signature = rsa_sig.signature().to_vec();
-    let message = attest.marshall()?;
-
-    context.clear_sessions();
-    let pcr_data = pcr::read_all(&mut context, selection_list)?;
-
-    let pcr_bank = pcr_data
-        .pcr_bank(hash_algo)
-        .ok_or(QuoteError::PcrBankNotFound)?;
-
-    let pcrs = pcr_bank
-        .into_iter()
-        .map(|(_, x)| x.value().to_vec())
-        .collect();
-
-    Ok(Quote {
-        signature,
-        message,
-        pcrs,
-    })
+    VtpmSession::open_device()?.get_quote(data)
+}
+
+/// Get a signed vTPM Quote over the selected PCRs
+///
+/// # Arguments
+///
+/// * `data` - A byte slice to use as nonce
+///
+/// * `pcr_selection` - The PCR bank and slots to quote
+pub fn get_quote_with_selection(
+    data: &[u8],
+    pcr_selection: &PcrSelection,
+) -> Result<Quote, QuoteError> {
+    VtpmSession::open_device()?.get_quote_with_selection(data, pcr_selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcr_selection() {
+        let selection = PcrSelection::new(PcrBank::Sha384, &[11, 0, 7, 4, 7]).unwrap();
+        assert_eq!(selection.bank(), PcrBank::Sha384);
+        assert_eq!(selection.slots(), [0, 4, 7, 11]);
+
+        let selection_list = selection.to_selection_list().unwrap();
+        let decoded = PcrSelection::from_selection_list(&selection_list).unwrap();
+        assert_eq!(decoded, selection);
+
+        assert!(matches!(
+            PcrSelection::new(PcrBank::Sha256, &[24]),
+            Err(QuoteError::InvalidPcrSlot(24))
+        ));
+    }
+
+    #[test]
+    fn quote_pcr_selection() {
+        let quote_bytes = include_bytes!("../../test/quote.bin");
+        let quote = Quote::from_bytes(quote_bytes).unwrap();
+        let selection = quote.pcr_selection().unwrap();
+        assert_eq!(selection, PcrSelection::default());
+    }
+
+    #[test]
+    fn deserialize_legacy_quote() {
+        let quote_bytes = include_bytes!("../../test/quote.bin");
+        let quote = Quote::from_bytes(quote_bytes).unwrap();
+        assert_eq!(quote.signature_scheme(), QuoteSignatureScheme::RsaSsaSha256);
+        assert_eq!(quote.pcr_bank(), PcrBank::Sha256);
+        let slots: Vec<u8> = quote.pcr_values().iter().map(|pcr| pcr.slot).collect();
+        assert_eq!(slots, (0..24).collect::<Vec<_>>());
+        assert!(quote.pcr_values().iter().all(|pcr| pcr.value.len() == 32));
+
+        let legacy_quote: LegacyQuote = bincode::deserialize(quote_bytes).unwrap();
+        let values: Vec<_> = quote.pcr_values().iter().map(|p| p.value.clone()).collect();
+        assert_eq!(values, legacy_quote.pcrs);
+    }
+
+    #[test]
+    fn serialize_versioned_quote() {
+        let quote_bytes = include_bytes!("../../test/quote.bin");
+        let quote = Quote::from_bytes(quote_bytes).unwrap();
+
+        let bytes = quote.to_bytes().unwrap();
+        assert_eq!(bytes[..8], [0; 8]);
+        assert_eq!(bytes[8..12], QUOTE_FORMAT_VERSION.to_le_bytes());
+        let decoded = Quote::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.message, quote.message);
+        assert_eq!(decoded.signature, quote.signature);
+        assert_eq!(decoded.pcr_values(), quote.pcr_values());
+
+        let json = serde_json::to_value(&quote).unwrap();
+        assert_eq!(json["version"], QUOTE_FORMAT_VERSION);
+        assert_eq!(json["pcr_bank"], "Sha256");
+        assert_eq!(json["pcrs"][7]["slot"], 7);
+        let decoded: Quote = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.pcr_values(), quote.pcr_values());
+    }
+
+    #[test]
+    fn deserialize_legacy_json_quote() {
+        let quote_bytes = include_bytes!("../../test/quote.bin");
+        let legacy_quote: LegacyQuote = bincode::deserialize(quote_bytes).unwrap();
+        let json = serde_json::to_value(&legacy_quote).unwrap();
+        let quote: Quote = serde_json::from_value(json).unwrap();
+        assert_eq!(quote.message, legacy_quote.message);
+        assert_eq!(quote.pcr_values().len(), legacy_quote.pcrs.len());
+    }
+
+    #[test]
+    fn reject_unsupported_quote_version() {
+        let quote_bytes = include_bytes!("../../test/quote.bin");
+        let quote = Quote::from_bytes(quote_bytes).unwrap();
+
+        let mut bytes = quote.to_bytes().unwrap();
+        bytes[8] = 2;
+        let result = Quote::from_bytes(&bytes);
+        assert!(
+            matches!(result, Err(QuoteError::Serialization(err)) if err.to_string().contains("version 2"))
+        );
+
+        let mut json = serde_json::to_value(&quote).unwrap();
+        json["version"] = 2.into();
+        assert!(serde_json::from_value::<Quote>(json).is_err());
+    }
 }
@@ -1,7 +1,7 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use super::{Quote, QuoteError};
+use super::{PcrSelection, Quote, QuoteError, QuoteSignatureScheme};
 use openssl::hash::MessageDigest;
 use openssl::pkey::{PKey, Public};
 use openssl::sign::Verifier;
@@ -25,6 +25,8 @@ pub enum VerifyError {
     Quote(#[from] QuoteError),
     #[error("pcr mismatch")]
     PcrMismatch,
+    #[error("bundled PCR values do not match the quote's PCR selection")]
+    PcrSelectionMismatch,
 }
 
 impl Quote {
@@ -54,7 +56,10 @@ impl Quote {
     ///
     /// * `pub_key` - A public key to verify the Quote's signature
     pub fn verify_signature(&self, pub_key: &PKey<Public>) -> Result<(), VerifyError> {
-        let mut verifier = Verifier::new(MessageDigest::sha256(), pub_key)?;
+        let digest = match self.signature_scheme {
+            QuoteSignatureScheme::RsaSsaSha256 => MessageDigest::sha256(),
+        };
+        let mut verifier = Verifier::new(digest, pub_key)?;
         verifier.update(&self.message)?;
         let is_verified = verifier.verify(&self.signature)?;
         if !is_verified {
@@ -63,20 +68,25 @@ impl Quote {
         Ok(())
     }
 
-    /// Verify that the TPM Quote's PCR digest matches the digest of the bundled PCR values
-    ///
+    /// Verify that the TPM Quote's PCR digest matches the digest of the bundled PCR values.
+    /// The values have to match the quote's PCR selection exactly, in the bank and in the order
+    /// of the selected slots. The digest is always SHA-256, the hash algorithm of the AK's
+    /// signing scheme, regardless of the bank.
     pub fn verify_pcrs(&self) -> Result<(), VerifyError> {
         let attest = Attest::unmarshall(&self.message)?;
         let AttestInfo::Quote { info } = attest.attested() else {
             return Err(VerifyError::Quote(QuoteError::NotAQuote));
         };
 
+        let pcr_selection = PcrSelection::from_selection_list(info.pcr_selection())?;
+        self.verify_pcr_selection(&pcr_selection)?;
+
         let pcr_digest = info.pcr_digest();
 
         // Read hashes of all the PCRs.
         let mut hasher = Sha256::new();
         for pcr in self.pcrs.iter() {
-            hasher.update(pcr);
+            hasher.update(&pcr.value);
         }
 
         let digest = hasher.finalize();
@@ -86,10 +96,27 @@ impl Quote {
 
         Ok(())
     }
+
+    /// Verify that the bundled PCR values are from the selected bank and slots
+    fn verify_pcr_selection(&self, pcr_selection: &PcrSelection) -> Result<(), VerifyError> {
+        let pcr_bank = pcr_selection.bank();
+        let slots = self.pcrs.iter().map(|pcr| pcr.slot);
+        if self.pcr_bank != pcr_bank
+            || !slots.eq(pcr_selection.slots().iter().copied())
+            || self
+                .pcrs
+                .iter()
+                .any(|pcr| pcr.value.len() != pcr_bank.digest_size())
+        {
+            return Err(VerifyError::PcrSelectionMismatch);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::super::PcrBank;
     use super::*;
 
     // // Use this code to generate the scriptures for the test on an AMD CVM.
@@ -163,5 +190,46 @@ mod tests {
         let quote: Quote = bincode::deserialize(quote_bytes).unwrap();
         let result = quote.verify_pcrs();
         assert!(result.is_ok(), "PCR verification should not fail");
+
+        // wrong value
+        let mut wrong_quote = quote.clone();
+        wrong_quote.pcrs[0].value[0] ^= 1;
+        let result = wrong_quote.verify_pcrs();
+        assert!(matches!(result, Err(VerifyError::PcrMismatch)));
+    }
+
+    #[test]
+    fn test_pcr_selection() {
+        let quote_bytes = include_bytes!("../../test/quote.bin");
+        let quote = Quote::from_bytes(quote_bytes).unwrap();
+
+        // values from a different bank
+        let mut wrong_quote = quote.clone();
+        wrong_quote.pcrs[0].value.truncate(20);
+        let result = wrong_quote.verify_pcrs();
+        assert!(matches!(result, Err(VerifyError::PcrSelectionMismatch)));
+
+        let mut wrong_quote = quote.clone();
+        wrong_quote.pcr_bank = PcrBank::Sha384;
+        let result = wrong_quote.verify_pcrs();
+        assert!(matches!(result, Err(VerifyError::PcrSelectionMismatch)));
+
+        // missing value
+        let mut wrong_quote = quote.clone();
+        wrong_quote.pcrs.pop();
+        let result = wrong_quote.verify_pcrs();
+        assert!(matches!(result, Err(VerifyError::PcrSelectionMismatch)));
+
+        // values in the wrong order
+        let mut wrong_quote = quote.clone();
+        wrong_quote.pcrs.swap(0, 1);
+        let result = wrong_quote.verify_pcrs();
+        assert!(matches!(result, Err(VerifyError::PcrSelectionMismatch)));
+
+        // value of a slot that was not quoted
+        let mut wrong_quote = quote.clone();
+        wrong_quote.pcrs[23].slot = 24;
+        let result = wrong_quote.verify_pcrs();
+        assert!(matches!(result, Err(VerifyError::PcrSelectionMismatch)));
     }
 }